@@ -1,9 +1,12 @@
 use std::collections::HashMap;
+use std::f32::consts::FRAC_PI_4;
+use std::fmt;
 
+use bevy::asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset};
 use bevy::prelude::*;
-use bevy_asset_loader::prelude::AssetCollectionApp;
-
-use crate::assets::ImageAssets;
+use bevy::reflect::TypeUuid;
+use bevy::utils::{BoxedFuture, Duration};
+use serde::Deserialize;
 
 #[derive(Bundle)]
 pub struct AnimationBundle {
@@ -12,197 +15,326 @@ pub struct AnimationBundle {
     animation: Animation,
 }
 
-#[derive(Component)]
-pub struct Animation {
-    frame: usize,
-    state_animations: HashMap<String, Vec<usize>>,
-    state: String,
-    repeat: bool,
+/// A sprite sheet and the named animations cut from it, loaded from a
+/// `*.anim.ron` file.
+#[derive(Deserialize, TypeUuid, Debug, Clone)]
+#[uuid = "ac63fceb-8312-4877-a51e-dd04049f7931"]
+pub struct AnimationDefinition {
+    /// Path of the sprite sheet, relative to the assets folder.
+    pub sheet: String,
+    pub cell_size: (f32, f32),
+    pub columns: usize,
+    pub rows: usize,
+    pub initial_state: AnimationKey,
+    pub states: HashMap<AnimationKey, AnimationState>,
+    /// Atlas built from the sheet by the loader.
+    #[serde(skip)]
+    pub atlas: Handle<TextureAtlas>,
 }
 
-impl Animation {
-    pub fn set_state(&mut self, state: String) {
-        self.state = state;
-    }
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
 }
 
-#[derive(Resource, Deref, DerefMut)]
-struct AnimationTimer(Timer);
+impl Direction {
+    // Diagonals go first so that "UpLeftWalking" isn't read as "Up" followed
+    // by an unknown action.
+    const ALL: [Direction; 8] = [
+        Direction::UpLeft,
+        Direction::UpRight,
+        Direction::DownLeft,
+        Direction::DownRight,
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
 
-pub struct AnimationPlugin;
-fn animate(
-    time: Res<Time>,
-    mut commands: Commands,
-    mut timer: ResMut<AnimationTimer>,
-    mut query: Query<(Entity, &mut Animation, &mut TextureAtlasSprite)>,
-) {
-    timer.tick(time.delta());
-    if timer.just_finished() {
-        for (entity, mut animation, mut sprite) in query.iter_mut() {
-            let curr_animation = animation.state_animations[&animation.state].clone();
-            animation.frame += 1;
-            if animation.frame >= curr_animation.len() {
-                if animation.repeat {
-                    animation.frame = 0;
-                } else {
-                    commands.entity(entity).despawn_recursive();
-                    continue;
-                }
-            }
-            sprite.index = curr_animation[animation.frame];
+    /// The closest of the eight directions to `vector`, counter-clockwise
+    /// from the positive x axis like [`f32::atan2`].
+    pub fn nearest(vector: Vec2) -> Self {
+        const OCTANTS: [Direction; 8] = [
+            Direction::Right,
+            Direction::UpRight,
+            Direction::Up,
+            Direction::UpLeft,
+            Direction::Left,
+            Direction::DownLeft,
+            Direction::Down,
+            Direction::DownRight,
+        ];
+        let octant = (vector.y.atan2(vector.x) / FRAC_PI_4).round() as i32;
+        OCTANTS[octant.rem_euclid(8) as usize]
+    }
+
+    /// Sheets without diagonal states show the side-facing ones instead.
+    fn cardinal(self) -> Self {
+        match self {
+            Direction::UpLeft | Direction::DownLeft => Direction::Left,
+            Direction::UpRight | Direction::DownRight => Direction::Right,
+            direction => direction,
         }
     }
 }
 
-impl Plugin for AnimationPlugin {
-    fn build(&self, app: &mut App) {
-        app.add_system(animate)
-            .insert_resource(AnimationTimer(Timer::from_seconds(
-                0.1,
-                TimerMode::Repeating,
-            )))
-            .init_collection::<ImageAssets>()
-            .init_resource::<GauchoAnimationResource>()
-            .init_resource::<FaconAnimationResource>()
-            .init_resource::<ZombieAnimationResource>();
-    }
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    Idle,
+    Walking,
+    Attack,
+    Death,
+}
+
+impl Action {
+    const ALL: [Action; 4] = [Action::Idle, Action::Walking, Action::Attack, Action::Death];
 }
 
-#[derive(Resource, Clone)]
-pub struct GauchoAnimationResource {
-    texture: Handle<TextureAtlas>,
-    state_animations: HashMap<String, Vec<usize>>,
-}
-
-impl FromWorld for GauchoAnimationResource {
-    fn from_world(world: &mut World) -> Self {
-        let image_asset = &world.get_resource::<ImageAssets>().unwrap().gaucho;
-        let texture_handle = image_asset.clone();
-        let mut texture_atlasses = world.get_resource_mut::<Assets<TextureAtlas>>().unwrap();
-        let texture_atlas =
-            TextureAtlas::from_grid(texture_handle, Vec2::new(16.0, 16.0), 3, 4, None, None);
-        let texture_atlas_handle = texture_atlasses.add(texture_atlas);
-        let mut state_animations = HashMap::new();
-        state_animations.insert("DownIdle".to_string(), vec![1]);
-        state_animations.insert("DownWalking".to_string(), vec![0, 1, 2, 1]);
-        state_animations.insert("LeftIdle".to_string(), vec![4]);
-        state_animations.insert("LeftWalking".to_string(), vec![3, 4, 5, 4]);
-        state_animations.insert("RightIdle".to_string(), vec![7]);
-        state_animations.insert("RightWalking".to_string(), vec![6, 7, 8, 7]);
-        state_animations.insert("UpIdle".to_string(), vec![10]);
-        state_animations.insert("UpWalking".to_string(), vec![9, 10, 11, 10]);
-        GauchoAnimationResource {
-            texture: texture_atlas_handle,
-            state_animations,
+/// Names an animation state. Written in `*.anim.ron` files as the direction
+/// followed by the action, e.g. `"LeftWalking"`, or just the action for
+/// sprites that don't face anywhere, e.g. `"Attack"`.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[serde(try_from = "String")]
+pub struct AnimationKey {
+    pub direction: Option<Direction>,
+    pub action: Action,
+}
+
+impl AnimationKey {
+    pub fn new(direction: Direction, action: Action) -> Self {
+        Self {
+            direction: Some(direction),
+            action,
         }
     }
 }
 
-impl From<GauchoAnimationResource> for AnimationBundle {
-    fn from(resource: GauchoAnimationResource) -> Self {
+impl From<Action> for AnimationKey {
+    fn from(action: Action) -> Self {
         Self {
-            sprite: SpriteSheetBundle {
-                texture_atlas: resource.texture,
-                sprite: TextureAtlasSprite::new(0),
-                transform: Transform::from_xyz(0., 0., 1.),
-                ..default()
-            },
-            animation: Animation {
-                frame: 0,
-                state_animations: resource.state_animations,
-                state: "DownWalking".to_string(),
-                repeat: true,
-            },
+            direction: None,
+            action,
         }
     }
 }
 
-#[derive(Resource, Clone)]
-pub struct ZombieAnimationResource {
-    texture: Handle<TextureAtlas>,
-    state_animations: HashMap<String, Vec<usize>>,
-}
-
-impl FromWorld for ZombieAnimationResource {
-    fn from_world(world: &mut World) -> Self {
-        let image_asset = &world.get_resource::<ImageAssets>().unwrap().zombie;
-        let texture_handle = image_asset.clone();
-        let mut texture_atlasses = world.get_resource_mut::<Assets<TextureAtlas>>().unwrap();
-        let texture_atlas =
-            TextureAtlas::from_grid(texture_handle, Vec2::new(16.0, 16.0), 3, 4, None, None);
-        let texture_atlas_handle = texture_atlasses.add(texture_atlas);
-        let mut state_animations = HashMap::new();
-        state_animations.insert("DownIdle".to_string(), vec![1]);
-        state_animations.insert("DownWalking".to_string(), vec![0, 1, 2, 1]);
-        state_animations.insert("LeftIdle".to_string(), vec![4]);
-        state_animations.insert("LeftWalking".to_string(), vec![3, 4, 5, 4]);
-        state_animations.insert("RightIdle".to_string(), vec![7]);
-        state_animations.insert("RightWalking".to_string(), vec![6, 7, 8, 7]);
-        state_animations.insert("UpIdle".to_string(), vec![10]);
-        state_animations.insert("UpWalking".to_string(), vec![9, 10, 11, 10]);
-        ZombieAnimationResource {
-            texture: texture_atlas_handle,
-            state_animations,
+impl TryFrom<String> for AnimationKey {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        let (direction, action) = Direction::ALL
+            .iter()
+            .find_map(|&direction| {
+                name.strip_prefix(&format!("{direction:?}"))
+                    .map(|action| (Some(direction), action))
+            })
+            .unwrap_or((None, name.as_str()));
+        let action = Action::ALL
+            .into_iter()
+            .find(|candidate| format!("{candidate:?}") == action)
+            .ok_or_else(|| format!("unknown animation state {name:?}"))?;
+        Ok(Self { direction, action })
+    }
+}
+
+impl fmt::Display for AnimationKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(direction) = self.direction {
+            write!(f, "{direction:?}")?;
         }
+        write!(f, "{:?}", self.action)
     }
 }
 
-impl From<ZombieAnimationResource> for AnimationBundle {
-    fn from(resource: ZombieAnimationResource) -> Self {
-        Self {
-            sprite: SpriteSheetBundle {
-                texture_atlas: resource.texture,
-                sprite: TextureAtlasSprite::new(0),
-                transform: Transform::from_xyz(0., 0., 1.),
-                ..default()
-            },
-            animation: Animation {
-                frame: 0,
-                state_animations: resource.state_animations,
-                state: "DownWalking".to_string(),
-                repeat: true,
-            },
+#[derive(Deserialize, Debug, Clone)]
+pub struct AnimationState {
+    /// Indices into the sheet, read left to right, top to bottom.
+    pub frames: Vec<usize>,
+    /// Seconds each frame stays on screen.
+    #[serde(default = "AnimationState::default_frame_duration")]
+    pub frame_duration: f32,
+    /// Start over after the last frame. Otherwise an [`AnimationFinished`]
+    /// event is sent when the state ends and the entity is despawned on the
+    /// next frame.
+    #[serde(default = "AnimationState::default_repeat")]
+    pub repeat: bool,
+}
+
+impl AnimationState {
+    fn default_frame_duration() -> f32 {
+        0.1
+    }
+
+    fn default_repeat() -> bool {
+        true
+    }
+}
+
+#[derive(Component)]
+pub struct Animation {
+    frame: usize,
+    state_animations: HashMap<AnimationKey, AnimationState>,
+    state: AnimationKey,
+    timer: Timer,
+    finished: bool,
+}
+
+impl Animation {
+    /// Switches to `state`, starting from its first frame. Setting the state
+    /// that is already playing does nothing. A diagonal state the sheet
+    /// doesn't define falls back to the side-facing one.
+    pub fn set_state(&mut self, mut state: AnimationKey) {
+        if !self.state_animations.contains_key(&state) {
+            state.direction = state.direction.map(Direction::cardinal);
+        }
+        if state == self.state {
+            return;
         }
+        self.state = state;
+        self.frame = 0;
+        self.finished = false;
+        let frame_duration = self.state_animations[&state].frame_duration;
+        self.timer
+            .set_duration(Duration::from_secs_f32(frame_duration));
+        self.timer.reset();
+    }
+}
+
+/// Label of the system that advances the animations, which runs in
+/// [`CoreStage::PostUpdate`].
+#[derive(SystemLabel)]
+pub struct AnimationSystem;
+
+/// Sent when an entity reaches the end of a state that doesn't repeat. The
+/// entity is still alive while the event is read, and is despawned on the
+/// next frame.
+pub struct AnimationFinished {
+    pub entity: Entity,
+    pub state: AnimationKey,
+}
+
+#[derive(Default)]
+struct AnimationDefinitionLoader;
+
+impl AssetLoader for AnimationDefinitionLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let mut definition: AnimationDefinition = ron::de::from_bytes(bytes)?;
+            let cells = definition.columns * definition.rows;
+            if !definition.states.contains_key(&definition.initial_state) {
+                return Err(bevy::asset::Error::msg(format!(
+                    "initial state {} isn't defined",
+                    definition.initial_state
+                )));
+            }
+            for (name, state) in &definition.states {
+                if state.frames.is_empty() {
+                    return Err(bevy::asset::Error::msg(format!(
+                        "state {name} has no frames"
+                    )));
+                }
+                if let Some(frame) = state.frames.iter().find(|&&frame| frame >= cells) {
+                    return Err(bevy::asset::Error::msg(format!(
+                        "state {name} uses frame {frame}, the sheet only has {cells}"
+                    )));
+                }
+            }
+
+            let sheet_path = AssetPath::from(definition.sheet.as_str()).to_owned();
+            let sheet = load_context.get_handle(sheet_path.clone());
+            let atlas = TextureAtlas::from_grid(
+                sheet,
+                Vec2::new(definition.cell_size.0, definition.cell_size.1),
+                definition.columns,
+                definition.rows,
+                None,
+                None,
+            );
+            definition.atlas = load_context.set_labeled_asset("atlas", LoadedAsset::new(atlas));
+            load_context
+                .set_default_asset(LoadedAsset::new(definition).with_dependency(sheet_path));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["anim.ron"]
     }
 }
 
-#[derive(Resource, Clone)]
-pub struct FaconAnimationResource {
-    texture: Handle<TextureAtlas>,
-    state_animations: HashMap<String, Vec<usize>>,
-}
-
-impl FromWorld for FaconAnimationResource {
-    fn from_world(world: &mut World) -> Self {
-        let image_asset = &world.get_resource::<ImageAssets>().unwrap().facon;
-        let texture_handle = image_asset.clone();
-        let mut texture_atlasses = world.get_resource_mut::<Assets<TextureAtlas>>().unwrap();
-        let texture_atlas =
-            TextureAtlas::from_grid(texture_handle, Vec2::new(16.0, 16.0), 1, 3, None, None);
-        let texture_atlas_handle = texture_atlasses.add(texture_atlas);
-        let mut state_animations = HashMap::new();
-        state_animations.insert("".to_string(), vec![0, 1, 2]);
-        FaconAnimationResource {
-            texture: texture_atlas_handle,
-            state_animations,
+fn animate(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut finished_events: EventWriter<AnimationFinished>,
+    mut query: Query<(Entity, &mut Animation, &mut TextureAtlasSprite)>,
+) {
+    for (entity, mut animation, mut sprite) in query.iter_mut() {
+        if animation.finished {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let animation = &mut *animation;
+        let curr_animation = &animation.state_animations[&animation.state];
+        animation.timer.tick(time.delta());
+        for _ in 0..animation.timer.times_finished_this_tick() {
+            if animation.frame + 1 < curr_animation.frames.len() {
+                animation.frame += 1;
+            } else if curr_animation.repeat {
+                animation.frame = 0;
+            } else {
+                animation.finished = true;
+                finished_events.send(AnimationFinished {
+                    entity,
+                    state: animation.state,
+                });
+                break;
+            }
         }
+        let index = curr_animation.frames[animation.frame];
+        if sprite.index != index {
+            sprite.index = index;
+        }
+    }
+}
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(CoreStage::PostUpdate, animate.label(AnimationSystem))
+            .add_event::<AnimationFinished>()
+            .add_asset::<AnimationDefinition>()
+            .init_asset_loader::<AnimationDefinitionLoader>();
     }
 }
 
-impl From<FaconAnimationResource> for AnimationBundle {
-    fn from(resource: FaconAnimationResource) -> Self {
+impl From<&AnimationDefinition> for AnimationBundle {
+    fn from(definition: &AnimationDefinition) -> Self {
+        let state = &definition.states[&definition.initial_state];
         Self {
             sprite: SpriteSheetBundle {
-                texture_atlas: resource.texture,
-                sprite: TextureAtlasSprite::new(0),
+                texture_atlas: definition.atlas.clone(),
+                sprite: TextureAtlasSprite::new(state.frames[0]),
                 transform: Transform::from_xyz(0., 0., 1.),
                 ..default()
             },
             animation: Animation {
                 frame: 0,
-                state_animations: resource.state_animations,
-                state: "".to_string(),
-                repeat: false,
+                state_animations: definition.states.clone(),
+                state: definition.initial_state,
+                timer: Timer::from_seconds(state.frame_duration, TimerMode::Repeating),
+                finished: false,
             },
         }
     }
@@ -0,0 +1,193 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::animations::AnimationDefinition;
+use crate::assets::AnimationAssets;
+use crate::pickups::{DropTable, BOSS_DROPS, COMMON_DROPS, TOUGH_DROPS};
+use crate::status::StatusKind;
+use crate::weapons::ProjectileSpec;
+
+#[derive(Component, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ZombieKind {
+    Walker,
+    Runner,
+    Tank,
+    Spitter,
+    Exploder,
+    Boss,
+}
+
+/// What a zombie does besides walking into the gaucho.
+#[derive(Clone, Copy, Debug)]
+pub enum Behavior {
+    Chase,
+    /// Keeps about `range` pixels away and spits every `cooldown` seconds.
+    Spit {
+        range: f32,
+        cooldown: f32,
+        damage: usize,
+        projectile: ProjectileSpec,
+    },
+    /// Blows up once it gets within `radius` pixels of the gaucho and he
+    /// can be hurt, hurting him and dying in the blast.
+    Explode {
+        radius: f32,
+        damage: usize,
+    },
+    /// Every `every` seconds, rushes at `multiplier` times its speed for
+    /// `duration` seconds.
+    Charge {
+        every: f32,
+        duration: f32,
+        multiplier: f32,
+    },
+}
+
+pub struct ZombieArchetype {
+    pub name: &'static str,
+    pub health: usize,
+    /// Pixels per second, before the wave's multiplier.
+    pub speed: f32,
+    pub contact_damage: usize,
+    /// Status effects its attacks apply to the gaucho.
+    pub inflicts: &'static [StatusKind],
+    /// Added to the score for each one killed.
+    pub points: u32,
+    /// Experience in the gem it drops.
+    pub experience: u32,
+    /// Rolled for a pickup when one dies.
+    pub drops: &'static DropTable,
+    /// Half extents of the collider, scaled along with the sprite.
+    pub collider: (f32, f32),
+    pub scale: f32,
+    pub behavior: Behavior,
+    /// Relative chance of this kind among those a wave allows.
+    pub spawn_weight: u32,
+    pub animation: fn(&AnimationAssets) -> &Handle<AnimationDefinition>,
+}
+
+impl ZombieKind {
+    pub fn archetype(&self) -> &'static ZombieArchetype {
+        match self {
+            ZombieKind::Walker => &WALKER,
+            ZombieKind::Runner => &RUNNER,
+            ZombieKind::Tank => &TANK,
+            ZombieKind::Spitter => &SPITTER,
+            ZombieKind::Exploder => &EXPLODER,
+            ZombieKind::Boss => &BOSS,
+        }
+    }
+}
+
+static WALKER: ZombieArchetype = ZombieArchetype {
+    name: "Walker",
+    health: 100,
+    speed: 50.,
+    contact_damage: 10,
+    inflicts: &[],
+    points: 10,
+    experience: 1,
+    drops: &COMMON_DROPS,
+    collider: (4., 8.),
+    scale: 1.,
+    behavior: Behavior::Chase,
+    spawn_weight: 10,
+    animation: |assets| &assets.zombie,
+};
+
+static RUNNER: ZombieArchetype = ZombieArchetype {
+    name: "Runner",
+    health: 60,
+    speed: 85.,
+    contact_damage: 8,
+    inflicts: &[StatusKind::Bleed],
+    points: 15,
+    experience: 2,
+    drops: &COMMON_DROPS,
+    collider: (3.5, 7.),
+    scale: 0.9,
+    behavior: Behavior::Chase,
+    spawn_weight: 6,
+    animation: |assets| &assets.zombie_runner,
+};
+
+static TANK: ZombieArchetype = ZombieArchetype {
+    name: "Tank",
+    health: 300,
+    speed: 30.,
+    contact_damage: 20,
+    inflicts: &[StatusKind::Slow],
+    points: 40,
+    experience: 5,
+    drops: &TOUGH_DROPS,
+    collider: (5., 8.),
+    scale: 1.4,
+    behavior: Behavior::Chase,
+    spawn_weight: 3,
+    animation: |assets| &assets.zombie_tank,
+};
+
+static SPITTER: ZombieArchetype = ZombieArchetype {
+    name: "Spitter",
+    health: 70,
+    speed: 45.,
+    contact_damage: 5,
+    inflicts: &[StatusKind::Slow],
+    points: 20,
+    experience: 2,
+    drops: &COMMON_DROPS,
+    collider: (4., 8.),
+    scale: 1.,
+    behavior: Behavior::Spit {
+        range: 140.,
+        cooldown: 2.,
+        damage: 8,
+        projectile: ProjectileSpec {
+            speed: 120.,
+            lifetime: 2.,
+            pierce: 0,
+            on_hit: &[],
+        },
+    },
+    spawn_weight: 4,
+    animation: |assets| &assets.zombie_spitter,
+};
+
+static EXPLODER: ZombieArchetype = ZombieArchetype {
+    name: "Exploder",
+    health: 50,
+    speed: 65.,
+    contact_damage: 0,
+    inflicts: &[StatusKind::Burn],
+    points: 15,
+    experience: 2,
+    drops: &COMMON_DROPS,
+    collider: (4., 8.),
+    scale: 1.,
+    behavior: Behavior::Explode {
+        radius: 24.,
+        damage: 30,
+    },
+    spawn_weight: 3,
+    animation: |assets| &assets.zombie_exploder,
+};
+
+static BOSS: ZombieArchetype = ZombieArchetype {
+    name: "Boss",
+    health: 1500,
+    speed: 35.,
+    contact_damage: 25,
+    inflicts: &[StatusKind::Bleed],
+    points: 250,
+    experience: 25,
+    drops: &BOSS_DROPS,
+    collider: (5., 8.),
+    scale: 2.,
+    behavior: Behavior::Charge {
+        every: 4.,
+        duration: 0.8,
+        multiplier: 3.,
+    },
+    spawn_weight: 1,
+    animation: |assets| &assets.zombie_boss,
+};
@@ -1,14 +1,57 @@
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::AssetCollection;
 
+use crate::animations::AnimationDefinition;
+use crate::progression::UpgradeTable;
+use crate::waves::WaveTable;
+
 #[derive(AssetCollection, Resource)]
 pub struct ImageAssets {
     #[asset(path = "StaticTiles.png")]
     pub tiles: Handle<Image>,
-    #[asset(path = "gaucho.png")]
-    pub gaucho: Handle<Image>,
-    #[asset(path = "zombie.png")]
-    pub zombie: Handle<Image>,
-    #[asset(path = "facon.png")]
-    pub facon: Handle<Image>,
+    /// The same tiles cut one by one, for props drawn as sprites.
+    #[asset(texture_atlas(tile_size_x = 16., tile_size_y = 16., columns = 11, rows = 13))]
+    #[asset(path = "StaticTiles.png")]
+    pub tile_atlas: Handle<TextureAtlas>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct AnimationAssets {
+    #[asset(path = "animations/gaucho.anim.ron")]
+    pub gaucho: Handle<AnimationDefinition>,
+    #[asset(path = "animations/zombie.anim.ron")]
+    pub zombie: Handle<AnimationDefinition>,
+    #[asset(path = "animations/zombie_runner.anim.ron")]
+    pub zombie_runner: Handle<AnimationDefinition>,
+    #[asset(path = "animations/zombie_tank.anim.ron")]
+    pub zombie_tank: Handle<AnimationDefinition>,
+    #[asset(path = "animations/zombie_spitter.anim.ron")]
+    pub zombie_spitter: Handle<AnimationDefinition>,
+    #[asset(path = "animations/zombie_exploder.anim.ron")]
+    pub zombie_exploder: Handle<AnimationDefinition>,
+    #[asset(path = "animations/zombie_boss.anim.ron")]
+    pub zombie_boss: Handle<AnimationDefinition>,
+    #[asset(path = "animations/spit.anim.ron")]
+    pub spit: Handle<AnimationDefinition>,
+    #[asset(path = "animations/facon.anim.ron")]
+    pub facon: Handle<AnimationDefinition>,
+    #[asset(path = "animations/boleadoras.anim.ron")]
+    pub boleadoras: Handle<AnimationDefinition>,
+    #[asset(path = "animations/trabuco_ball.anim.ron")]
+    pub trabuco_ball: Handle<AnimationDefinition>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct FontAssets {
+    #[asset(path = "fonts/FiraSans-Bold.ttf")]
+    pub main: Handle<Font>,
+}
+
+/// Gameplay tables kept out of the code, so they can be tuned on their own.
+#[derive(AssetCollection, Resource)]
+pub struct DataAssets {
+    #[asset(path = "waves/opening.waves.ron")]
+    pub waves: Handle<WaveTable>,
+    #[asset(path = "upgrades/gaucho.upgrades.ron")]
+    pub upgrades: Handle<UpgradeTable>,
 }
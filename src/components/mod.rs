@@ -1,5 +1,8 @@
 use bevy::prelude::*;
 
+use crate::animations::Direction;
+use crate::status::StatusKind;
+
 #[derive(Component)]
 pub struct Health {
     pub current: usize,
@@ -12,8 +15,102 @@ pub struct Gaucho;
 #[derive(Component)]
 pub struct Zombie;
 
+/// Pixels per second a zombie walks at.
+#[derive(Component, Deref, DerefMut)]
+pub struct Speed(pub f32);
+
+/// Paces a zombie's special move, such as a spitter's spit or a boss charge.
+#[derive(Component, Deref, DerefMut)]
+pub struct BehaviorTimer(pub Timer);
+
+/// Drawn in front of what stands north of it and behind what stands south,
+/// by keeping its depth in step with its height on screen.
+#[derive(Component)]
+pub struct YSort;
+
 #[derive(Component, Deref, DerefMut)]
 pub struct HitReaction(pub Vec2);
 
+/// What the player wants the gaucho to do this frame, whatever device they
+/// use. Filled in by `read_player_intent` from the [`crate::input`] bindings.
+#[derive(Component, Default)]
+pub struct PlayerIntent {
+    /// Walking direction, at most 1 long.
+    pub movement: Vec2,
+    /// Unit vector in world space, when the player pointed somewhere.
+    pub aim: Option<Vec2>,
+    pub attack: bool,
+    /// Weapon slot picked directly.
+    pub equip: Option<usize>,
+    /// Slots to move through the owned weapons, negative for backwards.
+    pub cycle_weapon: isize,
+}
+
+/// Where the gaucho points, as a unit vector in world space.
+#[derive(Component, Deref, DerefMut)]
+pub struct AimDirection(pub Vec2);
+
+impl AimDirection {
+    /// Radians counter-clockwise from the positive x axis.
+    pub fn angle(&self) -> f32 {
+        self.y.atan2(self.x)
+    }
+
+    pub fn facing(&self) -> Direction {
+        Direction::nearest(self.0)
+    }
+}
+
 #[derive(Component, Deref, DerefMut)]
 pub struct Damage(pub usize);
+
+/// Status effects a hit applies on top of its [`Damage`].
+#[derive(Component, Deref)]
+pub struct Inflicts(pub &'static [StatusKind]);
+
+/// How far a hit pushes its target back.
+#[derive(Component, Deref, DerefMut)]
+pub struct Knockback(pub f32);
+
+/// A thrown or fired attack flying in a straight line. It drops when it runs
+/// out of range or lifetime, or once it hit more targets than it can pierce.
+#[derive(Component)]
+pub struct Projectile {
+    pub velocity: Vec2,
+    /// Pixels left to fly.
+    pub range: f32,
+    pub lifetime: Timer,
+    /// Targets it passes through before stopping at the next one.
+    pub pierce: usize,
+    /// Shot by a zombie, so it hurts the gaucho instead of zombies.
+    pub hurts_gaucho: bool,
+    pub on_hit: &'static [HitEffect],
+    /// Targets already hit, so a piercing projectile hurts each one once.
+    pub hit: Vec<Entity>,
+}
+
+/// Extra effects a projectile applies where it hits, on top of its
+/// [`Damage`] and [`Knockback`].
+#[derive(Clone, Copy, Debug)]
+pub enum HitEffect {
+    Sound(&'static str),
+    /// Deals the projectile's damage to every other zombie within `radius`
+    /// pixels of the one hit.
+    Splash {
+        radius: f32,
+    },
+}
+
+/// Counts down after the gaucho takes a hit. Contacts don't hurt until it
+/// finishes, and the sprite flashes red meanwhile.
+#[derive(Component, Deref, DerefMut)]
+pub struct Invulnerability(pub Timer);
+
+impl Invulnerability {
+    pub fn from_seconds(duration: f32) -> Self {
+        let mut timer = Timer::from_seconds(duration, TimerMode::Once);
+        // Start out vulnerable.
+        timer.tick(timer.duration());
+        Self(timer)
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+
+use crate::systems::chunk::{chunk_tiles, tile_to_chunk, tile_to_world, world_to_tile};
+
+const NEIGHBORS: [IVec2; 4] = [IVec2::X, IVec2::Y, IVec2::NEG_X, IVec2::NEG_Y];
+const DIAGONALS: [IVec2; 4] = [
+    IVec2::new(1, 1),
+    IVec2::new(-1, 1),
+    IVec2::new(-1, -1),
+    IVec2::new(1, -1),
+];
+
+/// Steps from every reachable tile of the loaded chunks to the gaucho's tile,
+/// so zombies out of his sight can walk around solid tiles toward him.
+///
+/// The field is filled a bounded number of tiles per frame. Chunks that load
+/// extend the field from their borders, and the goal moving fixes up the
+/// distances toward the old goal; only a chunk unloading starts it over. Until
+/// the new field is done, zombies keep following the last complete one.
+#[derive(Default, Resource)]
+pub struct FlowField {
+    goal: Option<IVec2>,
+    chunks: HashSet<IVec2>,
+    solid: HashSet<IVec2>,
+    distances: HashMap<IVec2, u32>,
+    /// The field being rebuilt, when the one in `distances` is out of date.
+    rebuilding: Option<HashMap<IVec2, u32>>,
+    frontier: VecDeque<IVec2>,
+}
+
+impl FlowField {
+    pub fn goal(&self) -> Option<IVec2> {
+        self.goal
+    }
+
+    pub fn has_chunk(&self, chunk_pos: IVec2) -> bool {
+        self.chunks.contains(&chunk_pos)
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = IVec2> + '_ {
+        self.chunks.iter().copied()
+    }
+
+    fn is_walkable(&self, tile: IVec2) -> bool {
+        self.chunks.contains(&tile_to_chunk(tile)) && !self.solid.contains(&tile)
+    }
+
+    fn working(&mut self) -> &mut HashMap<IVec2, u32> {
+        self.rebuilding.as_mut().unwrap_or(&mut self.distances)
+    }
+
+    /// Moves the goal to `goal`. If the field already reaches it, no tile is
+    /// further from it than from the old goal plus the steps between the two,
+    /// so the field starts from those distances and only the tiles that got
+    /// closer are revisited. Otherwise it starts over.
+    pub fn retarget(&mut self, goal: IVec2) {
+        // Paths out of a goal nobody can walk on aren't paths at all.
+        let walkable = matches!(self.goal, Some(old) if self.is_walkable(old));
+        let offset = self.working().get(&goal).copied();
+        let (true, Some(offset)) = (walkable, offset) else {
+            self.restart(goal);
+            return;
+        };
+        self.goal = Some(goal);
+        let working = match &mut self.rebuilding {
+            Some(rebuilding) => rebuilding,
+            None => self.rebuilding.insert(self.distances.clone()),
+        };
+        for distance in working.values_mut() {
+            *distance += offset;
+        }
+        working.insert(goal, 0);
+        self.frontier.push_front(goal);
+    }
+
+    /// Starts over toward `goal`.
+    fn restart(&mut self, goal: IVec2) {
+        self.goal = Some(goal);
+        self.rebuilding = Some(HashMap::from_iter([(goal, 0)]));
+        self.frontier = VecDeque::from([goal]);
+    }
+
+    /// Adds a chunk with the given solid tiles to the field, spreading the
+    /// distances already known around it into it.
+    pub fn load_chunk(&mut self, chunk_pos: IVec2, solid: impl IntoIterator<Item = IVec2>) {
+        self.chunks.insert(chunk_pos);
+        self.solid.extend(solid);
+        let tiles: HashSet<IVec2> = chunk_tiles(chunk_pos).collect();
+        let mut border: Vec<IVec2> = tiles
+            .iter()
+            .flat_map(|&tile| NEIGHBORS.map(|step| tile + step))
+            .filter(|tile| !tiles.contains(tile))
+            .collect();
+        border.sort_by_key(|tile| (tile.y, tile.x));
+        border.dedup();
+        border.extend(self.goal.filter(|goal| tiles.contains(goal)));
+        let working = self.working();
+        let known: Vec<IVec2> = border
+            .into_iter()
+            .filter(|tile| working.contains_key(tile))
+            .collect();
+        self.frontier.extend(known);
+    }
+
+    /// Drops a chunk from the field. Paths may have gone through it, so the
+    /// field is rebuilt.
+    pub fn unload_chunk(&mut self, chunk_pos: IVec2) {
+        self.chunks.remove(&chunk_pos);
+        for tile in chunk_tiles(chunk_pos) {
+            self.solid.remove(&tile);
+        }
+        if let Some(goal) = self.goal {
+            self.restart(goal);
+        }
+    }
+
+    /// Lets paths through `tile`, which isn't solid anymore. Nothing gets
+    /// further from the goal, so the field only spreads from around it.
+    pub fn open_tile(&mut self, tile: IVec2) {
+        if !self.solid.remove(&tile) {
+            return;
+        }
+        let working = self.working();
+        let known: Vec<IVec2> = NEIGHBORS
+            .iter()
+            .map(|&step| tile + step)
+            .filter(|next| working.contains_key(next))
+            .collect();
+        self.frontier.extend(known);
+    }
+
+    /// Spreads the distances to at most `budget` more tiles.
+    pub fn advance(&mut self, budget: usize) {
+        for _ in 0..budget {
+            let Some(tile) = self.frontier.pop_front() else {
+                break;
+            };
+            let distance = self.working()[&tile] + 1;
+            for step in NEIGHBORS {
+                let next = tile + step;
+                if !self.is_walkable(next) {
+                    continue;
+                }
+                let working = self.working();
+                if working.get(&next).map_or(true, |&known| distance < known) {
+                    working.insert(next, distance);
+                    self.frontier.push_back(next);
+                }
+            }
+        }
+        if self.frontier.is_empty() {
+            if let Some(rebuilt) = self.rebuilding.take() {
+                self.distances = rebuilt;
+            }
+        }
+    }
+
+    /// Which way to walk from `position` to get closer to the goal, if the
+    /// field reaches there.
+    pub fn direction(&self, position: Vec2) -> Option<Vec2> {
+        let tile = world_to_tile(position);
+        let distance = *self.distances.get(&tile)?;
+        let orthogonal = NEIGHBORS
+            .iter()
+            .map(|&step| (step, tile + step))
+            .filter_map(|(step, next)| Some((step, *self.distances.get(&next)?)));
+        // Cutting a corner would scrape along a solid tile.
+        let diagonal = DIAGONALS
+            .iter()
+            .filter(|step| {
+                self.distances.contains_key(&(tile + IVec2::new(step.x, 0)))
+                    && self.distances.contains_key(&(tile + IVec2::new(0, step.y)))
+            })
+            .filter_map(|&step| Some((step, *self.distances.get(&(tile + step))?)));
+        let (step, best) = orthogonal
+            .chain(diagonal)
+            .min_by_key(|&(_, distance)| distance)?;
+        if best >= distance {
+            // On the goal already.
+            return None;
+        }
+        Some((tile_to_world(tile + step) - position).normalize_or_zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 3 by 3 chunk field with a wall across the middle, open at one end.
+    fn walled_field() -> FlowField {
+        let mut field = FlowField::default();
+        for y in -1..=1 {
+            for x in -1..=1 {
+                let chunk_pos = IVec2::new(x, y);
+                let wall = chunk_tiles(chunk_pos).filter(|tile| tile.y == 4 && tile.x > -6);
+                field.load_chunk(chunk_pos, wall);
+            }
+        }
+        field
+    }
+
+    fn settled(mut field: FlowField) -> HashMap<IVec2, u32> {
+        field.advance(usize::MAX);
+        field.distances
+    }
+
+    #[test]
+    fn moving_the_goal_gives_the_same_field_as_starting_over() {
+        let mut field = walled_field();
+        let mut goal = IVec2::new(2, 1);
+        field.retarget(goal);
+        field.advance(usize::MAX);
+        // Some steps are taken before the last field is done.
+        for step in [IVec2::Y, IVec2::Y, IVec2::Y, IVec2::NEG_X, IVec2::Y] {
+            goal += step;
+            field.retarget(goal);
+            field.advance(20);
+        }
+        let mut fresh = walled_field();
+        fresh.retarget(goal);
+        assert_eq!(settled(field), settled(fresh));
+    }
+}
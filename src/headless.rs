@@ -0,0 +1,371 @@
+//! Runs the gameplay loop without a window, GPU or audio device.
+//!
+//! The app is built from [`MinimalPlugins`], Rapier and the [`GamePlugin`].
+//! A virtual primary window stands in for the real one so the gameplay
+//! systems can keep reading the cursor, and an [`InputScript`] presses the
+//! keys and mouse buttons a player would. Time advances by a fixed step per
+//! tick and generated chunks are waited for, so a run only depends on the
+//! seed and the script.
+//!
+//! ```text
+//! cargo run -- --headless --ticks 3600 --seed 42
+//! ```
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use bevy::audio::{AudioLoader, AudioSink};
+use bevy::ecs::schedule::SingleThreadedExecutor;
+use bevy::input::{InputPlugin, InputSystem};
+use bevy::log::LogPlugin;
+use bevy::math::DVec2;
+use bevy::prelude::*;
+use bevy::render::texture::ImageTextureLoader;
+use bevy::text::FontLoader;
+use bevy::time::TimeUpdateStrategy;
+use bevy::utils::tracing::dispatcher;
+use bevy::window::WindowId;
+use bevy_embedded_assets::EmbeddedAssetPlugin;
+use bevy_rapier2d::prelude::*;
+
+use crate::components::{Gaucho, Zombie};
+use crate::progression::{UpgradeChosen, UpgradeOffer};
+use crate::resources::{GameStats, WorldSeed};
+use crate::systems::chunk::ChunkGenerator;
+use crate::{GamePlugin, GameState};
+
+const WINDOW_SIZE: Vec2 = Vec2::new(1280., 720.);
+const LOADING_POLL_INTERVAL: Duration = Duration::from_millis(1);
+/// Wall clock time allowed for the asset collection to finish loading.
+const MAX_LOADING_TIME: Duration = Duration::from_secs(30);
+
+/// Settings for a headless run, parsed from `--headless [--ticks N] [--tick-rate HZ]`.
+#[derive(Clone, Debug)]
+pub struct HeadlessConfig {
+    pub ticks: u32,
+    pub tick_rate: f64,
+    pub script: InputScript,
+}
+
+impl Default for HeadlessConfig {
+    fn default() -> Self {
+        Self {
+            ticks: 60 * 60,
+            tick_rate: 60.,
+            script: InputScript::default(),
+        }
+    }
+}
+
+impl HeadlessConfig {
+    /// Returns `None` unless `--headless` was passed.
+    pub fn from_args() -> Option<Self> {
+        let args: Vec<String> = std::env::args().collect();
+        if !args.iter().any(|arg| arg == "--headless") {
+            return None;
+        }
+        let value_of = |flag: &str| args.iter().skip_while(|arg| *arg != flag).nth(1);
+        let mut config = Self::default();
+        if let Some(ticks) = value_of("--ticks").and_then(|ticks| ticks.parse().ok()) {
+            config.ticks = ticks;
+        }
+        if let Some(rate) = value_of("--tick-rate").and_then(|rate| rate.parse().ok()) {
+            config.tick_rate = rate;
+        }
+        Some(config)
+    }
+}
+
+/// One leg of the scripted player: walk in `movement` for `ticks` ticks,
+/// attacking the closest zombie every `attack_every` ticks.
+#[derive(Clone, Debug)]
+pub struct ScriptStep {
+    pub ticks: u32,
+    pub movement: Vec<KeyCode>,
+    pub attack_every: Option<u32>,
+}
+
+/// Steps played in order and looped until the run is over.
+#[derive(Clone, Debug, Resource)]
+pub struct InputScript {
+    pub steps: Vec<ScriptStep>,
+}
+
+impl Default for InputScript {
+    /// Walks a square around the spawn point, stopping at each corner to let
+    /// the zombies catch up, and stabbing as it goes.
+    fn default() -> Self {
+        let stand = ScriptStep {
+            ticks: 150,
+            movement: vec![],
+            attack_every: Some(15),
+        };
+        let leg = |key| ScriptStep {
+            ticks: 60,
+            movement: vec![key],
+            attack_every: Some(15),
+        };
+        Self {
+            steps: [KeyCode::D, KeyCode::W, KeyCode::A, KeyCode::S]
+                .into_iter()
+                .flat_map(|key| [stand.clone(), leg(key)])
+                .collect(),
+        }
+    }
+}
+
+impl InputScript {
+    fn step_at(&self, tick: u32) -> Option<(&ScriptStep, u32)> {
+        let total: u32 = self.steps.iter().map(|step| step.ticks).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut tick = tick % total;
+        for step in &self.steps {
+            if tick < step.ticks {
+                return Some((step, tick));
+            }
+            tick -= step.ticks;
+        }
+        None
+    }
+}
+
+/// Outcome of a headless run.
+#[derive(PartialEq, Eq, Debug)]
+pub struct SimulationReport {
+    pub seed: u64,
+    pub ticks: u32,
+    pub waves_cleared: u32,
+    pub kills: usize,
+    pub score: u32,
+    pub coins: u32,
+    pub damage_taken: usize,
+    pub survival_time: Duration,
+}
+
+impl fmt::Display for SimulationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "seed: {}", self.seed)?;
+        writeln!(f, "ticks: {}", self.ticks)?;
+        writeln!(f, "waves_cleared: {}", self.waves_cleared)?;
+        writeln!(f, "kills: {}", self.kills)?;
+        writeln!(f, "score: {}", self.score)?;
+        writeln!(f, "coins: {}", self.coins)?;
+        writeln!(f, "damage_taken: {}", self.damage_taken)?;
+        write!(f, "survival_time: {:.2}s", self.survival_time.as_secs_f32())
+    }
+}
+
+#[derive(Resource, Default)]
+struct ScriptTick(u32);
+
+/// Runs up to `config.ticks` gameplay ticks, stopping early if the gaucho
+/// dies, and reports how the scripted player did.
+pub fn run(seed: WorldSeed, config: HeadlessConfig) -> SimulationReport {
+    let mut app = App::new();
+    // Logging can only be set up once per process, and the tests do
+    // several runs.
+    if !dispatcher::has_been_set() {
+        app.add_plugin(LogPlugin::default());
+    }
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(TransformPlugin)
+        .add_plugin(HierarchyPlugin)
+        .add_plugin(InputPlugin)
+        .add_plugin(WindowPlugin {
+            add_primary_window: false,
+            exit_on_all_closed: false,
+            ..default()
+        })
+        .add_plugin(EmbeddedAssetPlugin)
+        .add_plugin(AssetPlugin::default())
+        .add_asset::<Image>()
+        .init_asset_loader::<ImageTextureLoader>()
+        .add_asset::<TextureAtlas>()
+        .add_asset::<Font>()
+        .init_asset_loader::<FontLoader>()
+        .add_asset::<AudioSource>()
+        .init_asset_loader::<AudioLoader>()
+        .add_asset::<AudioSink>()
+        .init_resource::<Audio>()
+        .add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0))
+        .add_plugin(GamePlugin)
+        .insert_resource(seed)
+        // Chunks finishing on other threads would otherwise come in on
+        // whichever frame they happen to be done.
+        .insert_resource(ChunkGenerator::waiting())
+        .insert_resource(config.script.clone())
+        .init_resource::<ScriptTick>()
+        .add_startup_system(spawn_virtual_window)
+        .add_system_to_stage(CoreStage::PreUpdate, play_script.after(InputSystem))
+        .add_system_to_stage(CoreStage::PreUpdate, pick_upgrade)
+        .add_system_to_stage(CoreStage::Last, discard_audio);
+
+    // Systems without an explicit order between them run in whichever order
+    // the parallel executor picks, which would make each run different.
+    for stage in [
+        CoreStage::First.as_label(),
+        CoreStage::PreUpdate.as_label(),
+        CoreStage::Update.as_label(),
+        CoreStage::PostUpdate.as_label(),
+        CoreStage::Last.as_label(),
+        PhysicsStages::SyncBackend.as_label(),
+        PhysicsStages::StepSimulation.as_label(),
+        PhysicsStages::Writeback.as_label(),
+        PhysicsStages::DetectDespawn.as_label(),
+    ] {
+        app.stage(stage, |stage: &mut SystemStage| {
+            stage.set_executor(Box::<SingleThreadedExecutor>::default());
+            stage
+        });
+    }
+
+    // Game time stands still while the assets load, so timers that tick in
+    // every state start from the same phase regardless of how long that takes.
+    let start = Instant::now();
+    app.insert_resource(TimeUpdateStrategy::ManualInstant(start));
+    let mut loading_time = Duration::ZERO;
+    while app.world.resource::<State<GameState>>().current() == &GameState::Loading {
+        assert!(
+            loading_time < MAX_LOADING_TIME,
+            "assets didn't finish loading"
+        );
+        app.update();
+        std::thread::sleep(LOADING_POLL_INTERVAL);
+        loading_time += LOADING_POLL_INTERVAL;
+    }
+    // Nobody is there to click through the main menu.
+    app.world
+        .resource_mut::<State<GameState>>()
+        .set(GameState::Playing)
+        .unwrap();
+
+    let tick_length = Duration::from_secs_f64(1. / config.tick_rate);
+    let mut ticks = 0;
+    while ticks < config.ticks
+        && app.world.resource::<State<GameState>>().current() != &GameState::GameOver
+    {
+        ticks += 1;
+        app.insert_resource(TimeUpdateStrategy::ManualInstant(
+            start + tick_length * ticks,
+        ));
+        app.update();
+    }
+
+    let stats = app.world.resource::<GameStats>();
+    SimulationReport {
+        seed: seed.0,
+        ticks,
+        waves_cleared: stats.waves_cleared,
+        kills: stats.kills,
+        score: stats.score,
+        coins: stats.coins,
+        damage_taken: stats.damage_taken,
+        survival_time: stats.survival_time,
+    }
+}
+
+fn spawn_virtual_window(mut windows: ResMut<Windows>) {
+    windows.add(Window::new(
+        WindowId::primary(),
+        &WindowDescriptor::default(),
+        WINDOW_SIZE.x as u32,
+        WINDOW_SIZE.y as u32,
+        1.0,
+        None,
+        None,
+    ));
+}
+
+/// Feeds the current [`ScriptStep`] into the input resources and aims the
+/// virtual cursor at the closest zombie.
+#[allow(clippy::too_many_arguments)]
+fn play_script(
+    script: Res<InputScript>,
+    mut tick: ResMut<ScriptTick>,
+    state: Res<State<GameState>>,
+    mut keys: ResMut<Input<KeyCode>>,
+    mut buttons: ResMut<Input<MouseButton>>,
+    mut windows: ResMut<Windows>,
+    gaucho: Query<&Transform, With<Gaucho>>,
+    zombies: Query<&Transform, With<Zombie>>,
+) {
+    if state.current() != &GameState::Playing {
+        return;
+    }
+    let Some((step, step_tick)) = script.step_at(tick.0) else {
+        return;
+    };
+    tick.0 += 1;
+
+    let released: Vec<KeyCode> = keys
+        .get_pressed()
+        .filter(|key| !step.movement.contains(key))
+        .copied()
+        .collect();
+    for key in released {
+        keys.release(key);
+    }
+    for &key in &step.movement {
+        keys.press(key);
+    }
+    buttons.release(MouseButton::Left);
+    if matches!(step.attack_every, Some(every) if every > 0 && step_tick % every == 0) {
+        buttons.press(MouseButton::Left);
+    }
+
+    let Ok(gaucho) = gaucho.get_single() else {
+        return;
+    };
+    let gaucho = gaucho.translation.truncate();
+    let aim = zombies
+        .iter()
+        .map(|zombie| zombie.translation.truncate() - gaucho)
+        .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+        .unwrap_or(Vec2::X);
+    // The camera follows the gaucho, so the screen center is the gaucho.
+    let cursor = WINDOW_SIZE / 2. + aim.normalize_or_zero() * WINDOW_SIZE.y / 4.;
+    if let Some(window) = windows.get_primary_mut() {
+        window.update_cursor_physical_position_from_backend(Some(DVec2::new(
+            cursor.x as f64,
+            cursor.y as f64,
+        )));
+    }
+}
+
+/// Takes the first upgrade on offer at each level-up, so the run keeps going
+/// the same way for the same seed.
+fn pick_upgrade(
+    state: Res<State<GameState>>,
+    offer: Res<UpgradeOffer>,
+    mut chosen: EventWriter<UpgradeChosen>,
+) {
+    if state.current() != &GameState::LevelUp {
+        return;
+    }
+    if let Some(&upgrade) = offer.0.first() {
+        chosen.send(UpgradeChosen(upgrade));
+    }
+}
+
+/// Nothing plays the queued sounds without an audio device, so drop them.
+fn discard_audio(mut audio: ResMut<Audio>) {
+    *audio = Audio::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_and_script_give_the_same_run() {
+        let config = HeadlessConfig {
+            ticks: 1200,
+            ..default()
+        };
+        let first = run(WorldSeed(7), config.clone());
+        let second = run(WorldSeed(7), config);
+        assert_eq!(first, second);
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Things the player can bind keys, mouse buttons or gamepad buttons to.
+/// Moving and aiming with the gamepad sticks isn't rebindable.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum PlayerAction {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Attack,
+    NextWeapon,
+    PreviousWeapon,
+    Weapon1,
+    Weapon2,
+    Weapon3,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+}
+
+/// Which inputs trigger each [`PlayerAction`]. The game reads them from
+/// [`InputBindings::PATH`], so players can rebind actions by editing it.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct InputBindings {
+    pub actions: BTreeMap<PlayerAction, Vec<Binding>>,
+    /// How far, from 0 to 1, a stick has to be pushed before it counts.
+    pub stick_deadzone: f32,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        use Binding::*;
+        let actions = BTreeMap::from([
+            (
+                PlayerAction::MoveUp,
+                vec![Key(KeyCode::W), Key(KeyCode::Up)],
+            ),
+            (
+                PlayerAction::MoveDown,
+                vec![Key(KeyCode::S), Key(KeyCode::Down)],
+            ),
+            (
+                PlayerAction::MoveLeft,
+                vec![Key(KeyCode::A), Key(KeyCode::Left)],
+            ),
+            (
+                PlayerAction::MoveRight,
+                vec![Key(KeyCode::D), Key(KeyCode::Right)],
+            ),
+            (
+                PlayerAction::Attack,
+                vec![
+                    Mouse(MouseButton::Left),
+                    Gamepad(GamepadButtonType::RightTrigger2),
+                ],
+            ),
+            (
+                PlayerAction::NextWeapon,
+                vec![Key(KeyCode::E), Gamepad(GamepadButtonType::RightTrigger)],
+            ),
+            (
+                PlayerAction::PreviousWeapon,
+                vec![Key(KeyCode::Q), Gamepad(GamepadButtonType::LeftTrigger)],
+            ),
+            (PlayerAction::Weapon1, vec![Key(KeyCode::Key1)]),
+            (PlayerAction::Weapon2, vec![Key(KeyCode::Key2)]),
+            (PlayerAction::Weapon3, vec![Key(KeyCode::Key3)]),
+        ]);
+        Self {
+            actions,
+            stick_deadzone: 0.2,
+        }
+    }
+}
+
+impl InputBindings {
+    pub const PATH: &'static str = "bindings.ron";
+
+    /// Reads the bindings from `path`. When the file doesn't exist yet the
+    /// defaults are written there, so that there is something to edit.
+    pub fn load_or_create(path: &str) -> Self {
+        match fs::read_to_string(path) {
+            Ok(text) => ron::from_str(&text).unwrap_or_else(|error| {
+                warn!("Ignoring the bindings in {path}: {error}");
+                Self::default()
+            }),
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                let bindings = Self::default();
+                let written = ron::ser::to_string_pretty(&bindings, default())
+                    .map_err(|error| error.to_string())
+                    .and_then(|text| fs::write(path, text).map_err(|error| error.to_string()));
+                if let Err(error) = written {
+                    warn!("Couldn't write the default bindings to {path}: {error}");
+                }
+                bindings
+            }
+            Err(error) => {
+                warn!("Couldn't read the bindings in {path}: {error}");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn bindings(&self, action: PlayerAction) -> &[Binding] {
+        self.actions.get(&action).map_or(&[], Vec::as_slice)
+    }
+}
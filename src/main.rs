@@ -1,17 +1,31 @@
 mod animations;
+mod archetypes;
 mod assets;
 mod components;
+mod flow_field;
+mod headless;
+mod input;
+mod pickups;
+mod progression;
+mod props;
 pub mod resources;
+mod save;
+mod scoring;
+mod settings;
+mod status;
 mod systems;
+mod terrain;
+mod waves;
+mod weapons;
 
-use std::ops::Deref;
-
-use animations::{AnimationBundle, AnimationPlugin, GauchoAnimationResource};
-use assets::ImageAssets;
+use animations::{AnimationBundle, AnimationDefinition, AnimationPlugin, AnimationSystem};
+use assets::{AnimationAssets, DataAssets, FontAssets, ImageAssets};
 
 use bevy::{
+    audio::AudioSink,
     diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
     prelude::*,
+    transform::TransformSystem,
 };
 use bevy_asset_loader::prelude::{LoadingState, LoadingStateAppExt};
 use bevy_ecs_tilemap::prelude::*;
@@ -19,15 +33,40 @@ use bevy_embedded_assets::EmbeddedAssetPlugin;
 use noise::SuperSimplex;
 
 use bevy_rapier2d::prelude::*;
-use components::{Gaucho, HitReaction};
+use components::{AimDirection, Gaucho, Health, HitReaction, Invulnerability, PlayerIntent, YSort};
+use flow_field::FlowField;
+use input::InputBindings;
+use waves::{WaveCleared, WaveDirector, WaveStarted, WaveTable, WaveTableLoader};
+use weapons::Arsenal;
 
 #[derive(Clone, Eq, PartialEq, Debug, Hash)]
 enum GameState {
     Loading,
-    Next,
+    MainMenu,
+    Playing,
+    /// Pushed on top of [`GameState::Playing`], which resumes where it left.
+    Paused,
+    GameOver,
+    /// Pushed on top of the menu it was opened from.
+    Settings,
+    /// Pushed on top of [`GameState::Playing`] while an upgrade is picked.
+    LevelUp,
 }
 
+/// How loud the wind is, before the player's volume.
+const AMBIENCE_VOLUME: f32 = 0.3;
+
+/// The looping wind, so its volume follows the settings.
+#[derive(Resource)]
+struct Ambience(Handle<AudioSink>);
+
 fn main() {
+    let seed = resources::WorldSeed::from_args_or_env();
+    if let Some(config) = headless::HeadlessConfig::from_args() {
+        let report = headless::run(seed, config);
+        println!("{report}");
+        return;
+    }
     App::new()
         .add_plugins(
             DefaultPlugins
@@ -43,72 +82,371 @@ fn main() {
                 .add_before::<bevy::asset::AssetPlugin, _>(EmbeddedAssetPlugin),
         )
         .add_plugin(LogDiagnosticsPlugin::default())
-        .add_plugin(FrameTimeDiagnosticsPlugin::default())
+        .add_plugin(FrameTimeDiagnosticsPlugin)
         .add_plugin(TilemapPlugin)
-        .add_plugin(AnimationPlugin)
         .add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0))
         //.add_plugin(RapierDebugRenderPlugin::default())
-        .add_loading_state(
-            LoadingState::new(GameState::Loading)
-                .continue_to_state(GameState::Next)
-                .with_collection::<ImageAssets>(),
-        )
-        .add_state(GameState::Loading)
-        .add_system_set(SystemSet::on_enter(GameState::Next).with_system(setup))
-        .add_system_set(
-            SystemSet::on_update(GameState::Next)
-                .with_system(systems::gaucho::sprite_movement)
-                .with_system(
-                    systems::camera::camera_movement.after(systems::gaucho::sprite_movement),
-                )
-                .with_system(systems::gaucho::attack)
-                // .with_system(update_bullet_direction)
-                .with_system(systems::zombies::check_collisions)
-                .with_system(systems::zombies::spawn_wave)
-                .with_system(systems::zombies::update_zombies)
-                // .with_system(move_zombies)
-                .with_system(systems::chunk::spawn_chunks_around_camera)
-                .with_system(systems::chunk::despawn_outofrange_chunks),
-        )
-        .insert_resource(resources::WaveSpawnTimer(Timer::from_seconds(
-            1.0,
-            TimerMode::Repeating,
-        )))
+        .add_plugin(GamePlugin)
         .insert_resource(TilemapRenderSettings {
             render_chunk_size: systems::chunk::RENDER_CHUNK_SIZE,
         })
-        .insert_resource(resources::BulletTimer(Timer::from_seconds(
-            0.01,
-            TimerMode::Repeating,
-        )))
-        .insert_resource(resources::ChunkManager::default())
+        .insert_resource(seed)
+        .insert_resource(settings::GameSettings::load(settings::GameSettings::PATH))
+        .insert_resource(scoring::HighScores::load(scoring::HighScores::PATH))
+        .insert_resource(InputBindings::load_or_create(InputBindings::PATH))
         .run();
 }
 
-fn setup(
+/// Gameplay states, systems and resources. Doesn't depend on a renderer so
+/// it can run both in the windowed game and in [`headless`] simulations.
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(AnimationPlugin)
+            .add_asset::<WaveTable>()
+            .init_asset_loader::<WaveTableLoader>()
+            .add_asset::<progression::UpgradeTable>()
+            .init_asset_loader::<progression::UpgradeTableLoader>()
+            .add_loading_state(
+                LoadingState::new(GameState::Loading)
+                    .continue_to_state(GameState::MainMenu)
+                    .with_collection::<ImageAssets>()
+                    .with_collection::<AnimationAssets>()
+                    .with_collection::<FontAssets>()
+                    .with_collection::<DataAssets>(),
+            )
+            .add_state(GameState::Loading)
+            .add_system_set(SystemSet::on_exit(GameState::Loading).with_system(setup_scene))
+            .add_system_set(
+                SystemSet::on_enter(GameState::MainMenu)
+                    .with_system(systems::game_over::reset_world)
+                    .with_system(systems::menus::spawn_main_menu),
+            )
+            .add_system_set(
+                SystemSet::on_resume(GameState::MainMenu)
+                    .with_system(systems::menus::spawn_main_menu),
+            )
+            .add_system_set(
+                SystemSet::on_pause(GameState::MainMenu)
+                    .with_system(systems::menus::despawn_screen::<systems::menus::MainMenuScreen>),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::MainMenu)
+                    .with_system(systems::menus::despawn_screen::<systems::menus::MainMenuScreen>),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(systems::game_over::reset_world)
+                    .with_system(setup.after(systems::game_over::reset_world))
+                    .with_system(systems::hud::spawn_hud),
+            )
+            .add_system_set(
+                // Ordered end to end so a seeded run plays out the same every time.
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(systems::input::read_player_intent)
+                    .with_system(
+                        systems::gaucho::sprite_movement.after(systems::input::read_player_intent),
+                    )
+                    .with_system(
+                        systems::camera::camera_movement.after(systems::gaucho::sprite_movement),
+                    )
+                    .with_system(
+                        systems::props::loot_supplies.after(systems::camera::camera_movement),
+                    )
+                    .with_system(
+                        systems::gaucho::switch_weapon.after(systems::props::loot_supplies),
+                    )
+                    .with_system(systems::save::save_on_input.after(systems::gaucho::switch_weapon))
+                    .with_system(systems::gaucho::attack.after(systems::gaucho::switch_weapon))
+                    .with_system(
+                        systems::projectiles::move_projectiles.after(systems::gaucho::attack),
+                    )
+                    .with_system(
+                        systems::gaucho::track_survival_time
+                            .after(systems::projectiles::move_projectiles),
+                    )
+                    .with_system(
+                        systems::waves::direct_waves.after(systems::gaucho::track_survival_time),
+                    )
+                    .with_system(systems::waves::announce_waves.after(systems::waves::direct_waves))
+                    .with_system(
+                        systems::steering::update_spatial_hash
+                            .after(systems::waves::announce_waves),
+                    )
+                    .with_system(
+                        systems::zombies::update_zombies
+                            .after(systems::steering::update_spatial_hash),
+                    )
+                    // .with_system(move_zombies)
+                    .with_system(
+                        systems::zombies::zombie_specials.after(systems::zombies::update_zombies),
+                    )
+                    .with_system(
+                        systems::zombies::check_collisions.after(systems::zombies::zombie_specials),
+                    )
+                    .with_system(
+                        systems::status::apply_ground_statuses
+                            .after(systems::zombies::check_collisions),
+                    )
+                    .with_system(
+                        systems::status::apply_hazards
+                            .after(systems::status::apply_ground_statuses),
+                    )
+                    .with_system(
+                        systems::status::tick_status_effects.after(systems::status::apply_hazards),
+                    )
+                    .with_system(
+                        systems::scoring::score_kills.after(systems::status::tick_status_effects),
+                    )
+                    .with_system(systems::gaucho::check_death.after(systems::scoring::score_kills))
+                    .with_system(
+                        systems::progression::drop_experience.after(systems::gaucho::check_death),
+                    )
+                    .with_system(
+                        systems::progression::collect_experience
+                            .after(systems::progression::drop_experience),
+                    )
+                    .with_system(
+                        systems::pickups::drop_pickups
+                            .after(systems::progression::collect_experience),
+                    )
+                    .with_system(
+                        systems::pickups::collect_pickups.after(systems::pickups::drop_pickups),
+                    )
+                    .with_system(
+                        systems::pickups::expire_pickups.after(systems::pickups::collect_pickups),
+                    )
+                    .with_system(
+                        systems::pickups::wear_off_power_ups
+                            .after(systems::pickups::expire_pickups),
+                    )
+                    .with_system(
+                        systems::progression::offer_level_ups
+                            .after(systems::pickups::wear_off_power_ups),
+                    )
+                    .with_system(
+                        systems::chunk::queue_chunks_around_camera
+                            .after(systems::gaucho::check_death),
+                    )
+                    .with_system(
+                        systems::chunk::spawn_generated_chunks
+                            .after(systems::chunk::queue_chunks_around_camera),
+                    )
+                    .with_system(
+                        systems::chunk::despawn_outofrange_chunks
+                            .after(systems::chunk::spawn_generated_chunks),
+                    )
+                    .with_system(
+                        systems::steering::update_flow_field
+                            .after(systems::chunk::despawn_outofrange_chunks),
+                    ),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                systems::gaucho::sheathe_finished_weapons.after(AnimationSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                systems::props::y_sort.before(TransformSystem::TransformPropagate),
+            )
+            .add_system_to_stage(CoreStage::PostUpdate, systems::hud::update_health)
+            .add_system_to_stage(CoreStage::PostUpdate, systems::hud::update_weapon)
+            .add_system_to_stage(CoreStage::PostUpdate, systems::hud::update_experience)
+            .add_system_to_stage(CoreStage::PostUpdate, systems::hud::update_wave)
+            .add_system_to_stage(CoreStage::PostUpdate, systems::hud::update_stats)
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver)
+                    .with_system(systems::game_over::freeze_physics)
+                    .with_system(systems::scoring::record_high_score)
+                    .with_system(
+                        systems::game_over::spawn_game_over_screen
+                            .after(systems::scoring::record_high_score),
+                    ),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::GameOver)
+                    .with_system(systems::game_over::restart_on_input),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::Paused)
+                    .with_system(systems::menus::pause_game)
+                    .with_system(systems::menus::spawn_pause_menu),
+            )
+            .add_system_set(
+                SystemSet::on_resume(GameState::Paused)
+                    .with_system(systems::menus::spawn_pause_menu),
+            )
+            .add_system_set(
+                SystemSet::on_pause(GameState::Paused)
+                    .with_system(systems::menus::despawn_screen::<systems::menus::PauseScreen>),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Paused)
+                    .with_system(systems::menus::resume_game)
+                    .with_system(systems::menus::despawn_screen::<systems::menus::PauseScreen>),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::LevelUp)
+                    .with_system(systems::menus::pause_game)
+                    .with_system(systems::progression::roll_upgrades)
+                    .with_system(
+                        systems::progression::spawn_level_up_screen
+                            .after(systems::progression::roll_upgrades),
+                    ),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::LevelUp)
+                    .with_system(systems::progression::apply_upgrade),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::LevelUp)
+                    .with_system(systems::menus::resume_game)
+                    .with_system(
+                        systems::menus::despawn_screen::<systems::progression::LevelUpScreen>,
+                    ),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::Settings)
+                    .with_system(systems::menus::spawn_settings_screen),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Settings)
+                    .with_system(systems::menus::despawn_screen::<systems::menus::SettingsScreen>),
+            )
+            .add_system(systems::menus::back_on_escape)
+            .add_system(systems::menus::press_menu_buttons)
+            .add_system(systems::menus::highlight_buttons)
+            .add_system(systems::menus::update_setting_labels)
+            .add_system(systems::menus::apply_settings)
+            .add_system(systems::save::save_game)
+            .init_resource::<WaveDirector>()
+            .add_event::<systems::save::SaveRequested>()
+            .add_event::<scoring::ZombieKilled>()
+            .add_event::<progression::UpgradeChosen>()
+            .init_resource::<progression::UpgradeOffer>()
+            .init_resource::<scoring::Combo>()
+            .init_resource::<scoring::HighScores>()
+            .init_resource::<save::PendingLoad>()
+            .init_resource::<settings::GameSettings>()
+            .add_event::<WaveStarted>()
+            .add_event::<WaveCleared>()
+            .insert_resource(resources::BulletTimer(Timer::from_seconds(
+                0.01,
+                TimerMode::Repeating,
+            )))
+            .insert_resource(resources::ChunkManager::default())
+            .init_resource::<systems::chunk::ChunkGenerator>()
+            .init_resource::<systems::chunk::WorldStreamingSettings>()
+            .init_resource::<resources::GameStats>()
+            .init_resource::<resources::SpatialHash>()
+            .init_resource::<FlowField>()
+            .init_resource::<InputBindings>();
+    }
+}
+
+/// Things that outlive a single run: the camera and the ambient sound.
+fn setup_scene(
     mut commands: Commands,
-    gaucho_resource: Res<GauchoAnimationResource>,
     asset_server: Res<AssetServer>,
     audio: Res<Audio>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    settings: Res<settings::GameSettings>,
 ) {
     let mut camera = Camera2dBundle::default();
     camera.projection.scale = 0.25;
     commands.spawn(camera);
+    let wind = asset_server.load("sounds/wind.ogg");
+    let sink = audio.play_with_settings(
+        wind,
+        PlaybackSettings::LOOP.with_volume(AMBIENCE_VOLUME * settings.volume),
+    );
+    // Kept strong, or the sink would be dropped as soon as the wind starts.
+    commands.insert_resource(Ambience(audio_sinks.get_handle(sink)));
+}
+
+/// Starts a session: a fresh one, or the one in the [`save::PendingLoad`]
+/// if there is one.
+#[allow(clippy::too_many_arguments)]
+fn setup(
+    mut commands: Commands,
+    animation_assets: Res<AnimationAssets>,
+    animations: Res<Assets<AnimationDefinition>>,
+    data_assets: Res<DataAssets>,
+    wave_tables: Res<Assets<WaveTable>>,
+    mut seed: ResMut<resources::WorldSeed>,
+    mut launch_seed: Local<Option<resources::WorldSeed>>,
+    mut pending_load: ResMut<save::PendingLoad>,
+) {
+    // The seed the game was started with, which a loaded save only stands in
+    // for during its own session.
+    let launch_seed = *launch_seed.get_or_insert(*seed);
+    let save = pending_load.0.take();
+    let save = save.as_ref();
+    *seed = save.map_or(launch_seed, |save| save.seed());
+    let position = save.map_or(Vec2::ZERO, |save| save.gaucho.position);
     // Use only the subset of sprites in the sheet that make up the run animation
     commands
-        .spawn(Into::<AnimationBundle>::into(
-            gaucho_resource.deref().to_owned(),
+        .spawn(AnimationBundle::from(
+            animations.get(&animation_assets.gaucho).unwrap(),
         ))
         .insert(RigidBody::KinematicPositionBased)
         .insert(Collider::cuboid(4.0, 7.0))
+        // Only solid tiles stop the gaucho; zombies and projectiles are
+        // handled by the collision systems.
+        .insert(KinematicCharacterController {
+            filter_flags: QueryFilterFlags::EXCLUDE_DYNAMIC
+                | QueryFilterFlags::EXCLUDE_KINEMATIC
+                | QueryFilterFlags::EXCLUDE_SENSORS,
+            snap_to_ground: None,
+            ..default()
+        })
         .insert(TransformBundle::from(Transform::from_translation(
-            Vec3::new(0.0, 0.0, 1.0),
+            position.extend(1.0),
         )))
         .insert(HitReaction(Vec2::ZERO))
+        .insert(PlayerIntent::default())
+        .insert(AimDirection(Vec2::NEG_Y))
+        .insert(save.map_or(
+            Health {
+                current: 100,
+                max: 100,
+            },
+            |save| save.gaucho.health(),
+        ))
+        .insert(Invulnerability::from_seconds(
+            systems::zombies::GAUCHO_INVULNERABILITY,
+        ))
+        .insert(save.map_or_else(
+            || Arsenal::new(weapons::STARTING_WEAPONS.to_vec()),
+            |save| save.gaucho.arsenal(),
+        ))
+        .insert(save.map_or_else(default, |save| save.gaucho.experience))
+        .insert(save.map_or_else(default, |save| save.gaucho.upgrades.clone()))
+        .insert(save.map_or_else(default, |save| save.gaucho.power_ups.clone()))
+        .insert(save.map_or_else(default, |save| save.gaucho.statuses.clone()))
+        .insert(YSort)
         .insert(Gaucho);
 
-    let noise_fn = SuperSimplex::new(0);
+    info!("World seed: {}", seed.0);
+    let noise_fn = SuperSimplex::new(seed.noise_seed());
     commands.insert_resource(resources::Noise(Box::new(noise_fn)));
-    let wind = asset_server.load("sounds/wind.ogg");
-    audio.play_with_settings(wind, PlaybackSettings::LOOP.with_volume(0.3));
+    commands.insert_resource(terrain::Terrain::new(&seed));
+    commands.insert_resource(resources::WaveRng(seed.rng(resources::RngStream::Waves)));
+    commands.insert_resource(resources::CombatRng(seed.rng(resources::RngStream::Combat)));
+    commands.insert_resource(resources::UpgradeRng(
+        seed.rng(resources::RngStream::Upgrades),
+    ));
+    commands.insert_resource(resources::DropRng(seed.rng(resources::RngStream::Drops)));
+
+    if let Some(save) = save {
+        systems::save::restore_session(
+            &mut commands,
+            save,
+            &animation_assets,
+            &animations,
+            wave_tables.get(&data_assets.waves).unwrap(),
+        );
+        info!("Loaded the game from {}", save::SaveGame::PATH);
+    }
 }
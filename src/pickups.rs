@@ -0,0 +1,251 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::progression::GauchoStats;
+use crate::weapons::Arsenal;
+
+/// Seconds a pickup stays on the ground before it's gone.
+pub const PICKUP_LIFETIME: f32 = 30.;
+/// Seconds a power-up lasts once picked up.
+pub const POWER_UP_DURATION: f32 = 10.;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PickupKind {
+    Mate,
+    Asado,
+    Cana,
+    Espuelas,
+    Coin,
+    Weapon,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PowerUp {
+    /// Attacks twice as often.
+    Frenzy,
+    /// Walks half as fast again.
+    Haste,
+}
+
+/// What picking something up does for the gaucho.
+#[derive(Clone, Copy, Debug)]
+pub enum PickupEffect {
+    Heal(usize),
+    /// Lasts [`POWER_UP_DURATION`] seconds, picking another one again starts
+    /// it over.
+    PowerUp(PowerUp),
+    Currency(u32),
+    /// Unlocks the next weapon he doesn't have yet.
+    Weapon,
+}
+
+pub struct PickupDefinition {
+    pub name: &'static str,
+    pub effect: PickupEffect,
+    pub color: Color,
+    pub size: Vec2,
+}
+
+impl PickupKind {
+    pub fn definition(&self) -> &'static PickupDefinition {
+        match self {
+            PickupKind::Mate => &MATE,
+            PickupKind::Asado => &ASADO,
+            PickupKind::Cana => &CANA,
+            PickupKind::Espuelas => &ESPUELAS,
+            PickupKind::Coin => &COIN,
+            PickupKind::Weapon => &WEAPON,
+        }
+    }
+
+    /// What is actually dropped for a gaucho with `arsenal`: with no weapon
+    /// left to unlock, a weapon is an asado instead.
+    pub fn for_arsenal(self, arsenal: &Arsenal) -> Self {
+        if self == PickupKind::Weapon && arsenal.next_locked().is_none() {
+            PickupKind::Asado
+        } else {
+            self
+        }
+    }
+}
+
+static MATE: PickupDefinition = PickupDefinition {
+    name: "Mate",
+    effect: PickupEffect::Heal(15),
+    color: Color::rgb(0.45, 0.7, 0.25),
+    size: Vec2::new(4., 5.),
+};
+
+static ASADO: PickupDefinition = PickupDefinition {
+    name: "Asado",
+    effect: PickupEffect::Heal(50),
+    color: Color::rgb(0.6, 0.25, 0.15),
+    size: Vec2::new(7., 4.),
+};
+
+static CANA: PickupDefinition = PickupDefinition {
+    name: "Caña",
+    effect: PickupEffect::PowerUp(PowerUp::Frenzy),
+    color: Color::rgb(0.95, 0.55, 0.2),
+    size: Vec2::new(3., 6.),
+};
+
+static ESPUELAS: PickupDefinition = PickupDefinition {
+    name: "Espuelas",
+    effect: PickupEffect::PowerUp(PowerUp::Haste),
+    color: Color::rgb(0.75, 0.75, 0.8),
+    size: Vec2::new(5., 5.),
+};
+
+static COIN: PickupDefinition = PickupDefinition {
+    name: "Peso",
+    effect: PickupEffect::Currency(1),
+    color: Color::rgb(1., 0.85, 0.2),
+    size: Vec2::new(3., 3.),
+};
+
+static WEAPON: PickupDefinition = PickupDefinition {
+    name: "Weapon",
+    effect: PickupEffect::Weapon,
+    color: Color::rgb(0.9, 0.9, 1.),
+    size: Vec2::new(8., 3.),
+};
+
+/// What a zombie may leave behind when it dies.
+pub struct DropTable {
+    /// Chance, from 0 to 1, that it drops anything at all.
+    pub chance: f32,
+    /// Each pickup with its relative chance among the others.
+    pub entries: &'static [(PickupKind, u32)],
+}
+
+impl DropTable {
+    pub fn roll(&self, random: &mut impl Rng) -> Option<PickupKind> {
+        if !random.gen_bool(self.chance as f64) {
+            return None;
+        }
+        self.entries
+            .choose_weighted(random, |&(_, weight)| weight)
+            .ok()
+            .map(|&(kind, _)| kind)
+    }
+}
+
+pub static COMMON_DROPS: DropTable = DropTable {
+    chance: 0.15,
+    entries: &[
+        (PickupKind::Coin, 12),
+        (PickupKind::Mate, 5),
+        (PickupKind::Asado, 1),
+        (PickupKind::Cana, 1),
+        (PickupKind::Espuelas, 1),
+    ],
+};
+
+pub static TOUGH_DROPS: DropTable = DropTable {
+    chance: 0.5,
+    entries: &[
+        (PickupKind::Coin, 6),
+        (PickupKind::Asado, 3),
+        (PickupKind::Cana, 2),
+        (PickupKind::Espuelas, 2),
+        (PickupKind::Weapon, 1),
+    ],
+};
+
+pub static BOSS_DROPS: DropTable = DropTable {
+    chance: 1.,
+    entries: &[(PickupKind::Weapon, 1)],
+};
+
+/// Something lying on the ground of a chunk, waiting to be picked up. It's a
+/// child of the chunk's tilemap, and is written into the chunk's delta when
+/// the chunk is unloaded.
+#[derive(Component)]
+pub struct Pickup {
+    pub kind: PickupKind,
+    pub chunk: IVec2,
+    /// Runs out when the pickup is gone.
+    pub lifetime: Timer,
+}
+
+impl Pickup {
+    /// How to keep it in a chunk delta, `transform` being its own.
+    pub fn saved(&self, transform: &Transform) -> SavedPickup {
+        SavedPickup {
+            kind: self.kind,
+            offset: transform.translation.truncate(),
+            remaining: self.lifetime.remaining_secs(),
+        }
+    }
+}
+
+/// A [`Pickup`] kept in a chunk's delta while the chunk isn't loaded.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct SavedPickup {
+    pub kind: PickupKind,
+    /// Relative to the chunk's origin.
+    pub offset: Vec2,
+    /// Seconds left before it's gone. The clock stops while nobody's there.
+    pub remaining: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct ActivePowerUp {
+    pub kind: PowerUp,
+    /// Seconds left before it wears off.
+    pub remaining: f32,
+}
+
+/// The power-ups the gaucho has going.
+#[derive(Component, Serialize, Deserialize, Clone, Default, Debug)]
+pub struct PowerUps(pub Vec<ActivePowerUp>);
+
+impl PowerUps {
+    pub fn start(&mut self, kind: PowerUp) {
+        self.0.retain(|active| active.kind != kind);
+        self.0.push(ActivePowerUp {
+            kind,
+            remaining: POWER_UP_DURATION,
+        });
+    }
+
+    pub fn tick(&mut self, seconds: f32) {
+        for active in &mut self.0 {
+            active.remaining -= seconds;
+        }
+        self.0.retain(|active| active.remaining > 0.);
+    }
+
+    /// `stats` with the running power-ups on top.
+    pub fn boost(&self, mut stats: GauchoStats) -> GauchoStats {
+        for active in &self.0 {
+            match active.kind {
+                PowerUp::Frenzy => stats.cooldown /= 2.,
+                PowerUp::Haste => stats.move_speed *= 1.5,
+            }
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weapons::{WeaponKind, STARTING_WEAPONS};
+
+    #[test]
+    fn weapon_drops_unlock_the_weapons_not_started_with() {
+        let mut arsenal = Arsenal::new(STARTING_WEAPONS.to_vec());
+        while arsenal.owned.len() < WeaponKind::ALL.len() {
+            assert_eq!(PickupKind::Weapon.for_arsenal(&arsenal), PickupKind::Weapon);
+            let owned = arsenal.owned.len();
+            arsenal.unlock(arsenal.next_locked().unwrap());
+            assert_eq!(arsenal.owned.len(), owned + 1);
+        }
+        assert_eq!(PickupKind::Weapon.for_arsenal(&arsenal), PickupKind::Asado);
+        assert_eq!(PickupKind::Mate.for_arsenal(&arsenal), PickupKind::Mate);
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::weapons::Arsenal;
+
+/// Pixels the gaucho walks each frame, before upgrades.
+pub const BASE_MOVE_SPEED: f32 = 2.0;
+/// Pixels from the gaucho that experience gems get pulled from, before
+/// upgrades.
+pub const BASE_MAGNET_RADIUS: f32 = 40.0;
+/// Upgrades offered at each level-up.
+pub const UPGRADES_OFFERED: usize = 3;
+
+/// Experience dropped by a killed zombie, waiting to be picked up.
+#[derive(Component)]
+pub struct ExperienceGem {
+    pub experience: u32,
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Experience {
+    pub level: u32,
+    /// Gathered towards the next level.
+    pub points: u32,
+    /// Level-ups whose upgrade hasn't been picked yet.
+    pub pending: u32,
+}
+
+impl Default for Experience {
+    fn default() -> Self {
+        Self {
+            level: 1,
+            points: 0,
+            pending: 0,
+        }
+    }
+}
+
+impl Experience {
+    /// Experience it takes to go from `level` to the next one.
+    pub fn needed(level: u32) -> u32 {
+        5 + 5 * (level - 1)
+    }
+
+    /// Adds experience, levelling up as many times as it's enough for.
+    pub fn gain(&mut self, points: u32) {
+        self.points += points;
+        while self.points >= Self::needed(self.level) {
+            self.points -= Self::needed(self.level);
+            self.level += 1;
+            self.pending += 1;
+        }
+    }
+
+    /// How far, from 0 to 1, the gaucho is into the current level.
+    pub fn progress(&self) -> f32 {
+        self.points as f32 / Self::needed(self.level) as f32
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Upgrade {
+    Damage,
+    AttackSpeed,
+    MoveSpeed,
+    MaxHealth,
+    Magnet,
+    Weapon,
+    Heal,
+}
+
+/// What an upgrade changes. Bonuses of the same kind add up before they're
+/// applied to the base stats.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum Effect {
+    /// Fraction added to the damage of every hit.
+    Damage(f32),
+    /// Fraction added to how often the gaucho attacks.
+    AttackSpeed(f32),
+    /// Fraction added to how fast he walks.
+    MoveSpeed(f32),
+    MaxHealth(usize),
+    /// Fraction added to how far gems get pulled from.
+    Magnet(f32),
+    /// Unlocks the next weapon he doesn't have yet.
+    Weapon,
+    /// Health given back, up to the max.
+    Heal(usize),
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UpgradeDefinition {
+    pub name: String,
+    pub description: String,
+    pub effect: Effect,
+    /// Times it can be picked in a run, as many as it's offered if `None`.
+    #[serde(default)]
+    pub max_stacks: Option<usize>,
+}
+
+impl Upgrade {
+    pub const ALL: [Upgrade; 6] = [
+        Upgrade::Damage,
+        Upgrade::AttackSpeed,
+        Upgrade::MoveSpeed,
+        Upgrade::MaxHealth,
+        Upgrade::Magnet,
+        Upgrade::Weapon,
+    ];
+    /// Offered on its own once none of [`Upgrade::ALL`] can be taken, so a
+    /// level-up always has something to pick.
+    pub const FALLBACK: Upgrade = Upgrade::Heal;
+}
+
+/// What every upgrade does, loaded from a `*.upgrades.ron` file.
+#[derive(Deserialize, TypeUuid, Debug)]
+#[uuid = "9e4b7a31-6c2d-4f85-b0a3-2d81e5c7f946"]
+pub struct UpgradeTable(pub HashMap<Upgrade, UpgradeDefinition>);
+
+impl UpgradeTable {
+    pub fn definition(&self, upgrade: Upgrade) -> &UpgradeDefinition {
+        &self.0[&upgrade]
+    }
+}
+
+#[derive(Default)]
+pub struct UpgradeTableLoader;
+
+impl AssetLoader for UpgradeTableLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let table: UpgradeTable = ron::de::from_bytes(bytes)?;
+            if let Some(missing) = Upgrade::ALL
+                .into_iter()
+                .chain([Upgrade::FALLBACK])
+                .find(|upgrade| !table.0.contains_key(upgrade))
+            {
+                return Err(bevy::asset::Error::msg(format!(
+                    "{missing:?} isn't defined"
+                )));
+            }
+            load_context.set_default_asset(LoadedAsset::new(table));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["upgrades.ron"]
+    }
+}
+
+/// The gaucho's stats once his upgrades are stacked on the base ones.
+#[derive(Clone, Copy, Debug)]
+pub struct GauchoStats {
+    pub move_speed: f32,
+    pub damage: f32,
+    /// What weapon cooldowns are multiplied by.
+    pub cooldown: f32,
+    pub magnet_radius: f32,
+}
+
+/// Upgrades picked so far in the run, in the order they were picked.
+#[derive(Component, Serialize, Deserialize, Clone, Default, Debug)]
+pub struct Upgrades(pub Vec<Upgrade>);
+
+impl Upgrades {
+    pub fn stacks(&self, upgrade: Upgrade) -> usize {
+        self.0.iter().filter(|&&taken| taken == upgrade).count()
+    }
+
+    pub fn stats(&self, table: &UpgradeTable) -> GauchoStats {
+        let (mut damage, mut attack_speed, mut move_speed, mut magnet) = (0., 0., 0., 0.);
+        for &upgrade in &self.0 {
+            match table.definition(upgrade).effect {
+                Effect::Damage(bonus) => damage += bonus,
+                Effect::AttackSpeed(bonus) => attack_speed += bonus,
+                Effect::MoveSpeed(bonus) => move_speed += bonus,
+                Effect::Magnet(bonus) => magnet += bonus,
+                // Applied once, when picked.
+                Effect::MaxHealth(_) | Effect::Weapon | Effect::Heal(_) => {}
+            }
+        }
+        GauchoStats {
+            move_speed: BASE_MOVE_SPEED * (1. + move_speed),
+            damage: 1. + damage,
+            cooldown: 1. / (1. + attack_speed),
+            magnet_radius: BASE_MAGNET_RADIUS * (1. + magnet),
+        }
+    }
+
+    /// Picks up to [`UPGRADES_OFFERED`] different upgrades that can still be
+    /// taken, or [`Upgrade::FALLBACK`] when there's none left.
+    pub fn offer(
+        &self,
+        table: &UpgradeTable,
+        arsenal: &Arsenal,
+        random: &mut impl Rng,
+    ) -> Vec<Upgrade> {
+        let available: Vec<Upgrade> = Upgrade::ALL
+            .into_iter()
+            .filter(|&upgrade| {
+                let max_stacks = table.definition(upgrade).max_stacks;
+                max_stacks.map_or(true, |max| self.stacks(upgrade) < max)
+            })
+            .filter(|&upgrade| upgrade != Upgrade::Weapon || arsenal.next_locked().is_some())
+            .collect();
+        if available.is_empty() {
+            return vec![Upgrade::FALLBACK];
+        }
+        available
+            .choose_multiple(random, UPGRADES_OFFERED)
+            .copied()
+            .collect()
+    }
+}
+
+/// The upgrades the level-up screen is offering.
+#[derive(Resource, Default)]
+pub struct UpgradeOffer(pub Vec<Upgrade>);
+
+/// Sent when the player picks one of the [`UpgradeOffer`].
+pub struct UpgradeChosen(pub Upgrade);
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::weapons::{WeaponKind, STARTING_WEAPONS};
+
+    fn table() -> UpgradeTable {
+        ron::from_str(include_str!("../assets/upgrades/gaucho.upgrades.ron")).unwrap()
+    }
+
+    #[test]
+    fn offers_the_fallback_once_everything_is_maxed() {
+        let table = table();
+        let arsenal = Arsenal::new(WeaponKind::ALL.to_vec());
+        let mut upgrades = Upgrades::default();
+        for upgrade in Upgrade::ALL {
+            let max = table.definition(upgrade).max_stacks.unwrap_or(0);
+            upgrades.0.extend(std::iter::repeat(upgrade).take(max));
+        }
+        let mut random = StdRng::seed_from_u64(0);
+        assert_eq!(
+            upgrades.offer(&table, &arsenal, &mut random),
+            [Upgrade::FALLBACK]
+        );
+    }
+
+    #[test]
+    fn never_offers_the_fallback_while_something_else_is_left() {
+        let table = table();
+        let arsenal = Arsenal::new(STARTING_WEAPONS.to_vec());
+        let mut random = StdRng::seed_from_u64(0);
+        let offer = Upgrades::default().offer(&table, &arsenal, &mut random);
+        assert_eq!(offer.len(), UPGRADES_OFFERED);
+        assert!(!offer.contains(&Upgrade::FALLBACK));
+    }
+
+    #[test]
+    fn offers_a_new_weapon_while_one_is_locked() {
+        let table = table();
+        let arsenal = Arsenal::new(STARTING_WEAPONS.to_vec());
+        let mut upgrades = Upgrades::default();
+        for upgrade in Upgrade::ALL {
+            let max = table.definition(upgrade).max_stacks.unwrap_or(0);
+            upgrades.0.extend(std::iter::repeat(upgrade).take(max));
+        }
+        let mut random = StdRng::seed_from_u64(0);
+        assert_eq!(
+            upgrades.offer(&table, &arsenal, &mut random),
+            [Upgrade::Weapon]
+        );
+    }
+}
@@ -0,0 +1,241 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::resources::{RngStream, WorldSeed};
+use crate::systems::chunk::{chunk_tiles, tile_to_chunk};
+use crate::terrain::{Terrain, SPAWN_CLEARING};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PropKind {
+    Ombu,
+    Fence,
+    Estancia,
+    Well,
+    Carcass,
+    /// An abandoned pulpería, with a chest of supplies left behind.
+    Pulperia,
+}
+
+/// One tile of a prop's sprite.
+pub struct PropPart {
+    /// Tiles from the prop's anchor.
+    pub offset: IVec2,
+    pub texture: u32,
+    /// Half extents of the collider on this tile, when nothing walks through
+    /// it.
+    pub collider: Option<Vec2>,
+    /// What the gaucho takes the first time he reaches it.
+    pub supplies: Option<Supplies>,
+}
+
+/// Which of the props generated for its chunk a prop entity is, so changes
+/// to it outlive the chunk.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Prop {
+    pub kind: PropKind,
+    pub chunk: IVec2,
+    pub index: usize,
+    /// Grid position of the prop's anchor tile.
+    pub anchor: IVec2,
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Supplies {
+    pub health: usize,
+}
+
+pub struct PropDefinition {
+    pub name: &'static str,
+    pub parts: &'static [PropPart],
+    /// Whether a blast breaks it down.
+    pub breakable: bool,
+}
+
+impl PropKind {
+    pub fn definition(&self) -> &'static PropDefinition {
+        match self {
+            PropKind::Ombu => &OMBU,
+            PropKind::Fence => &FENCE,
+            PropKind::Estancia => &ESTANCIA,
+            PropKind::Well => &WELL,
+            PropKind::Carcass => &CARCASS,
+            PropKind::Pulperia => &PULPERIA,
+        }
+    }
+}
+
+impl PropDefinition {
+    /// Tiles of the parts nothing walks through, with the prop anchored at
+    /// `anchor`.
+    pub fn solid_tiles(&self, anchor: IVec2) -> impl Iterator<Item = IVec2> + '_ {
+        self.parts
+            .iter()
+            .filter(|part| part.collider.is_some())
+            .map(move |part| anchor + part.offset)
+    }
+}
+
+const fn part(x: i32, y: i32, texture: u32, collider: Option<Vec2>) -> PropPart {
+    PropPart {
+        offset: IVec2::new(x, y),
+        texture,
+        collider,
+        supplies: None,
+    }
+}
+
+/// A collider filling most of a tile.
+const BLOCK: Option<Vec2> = Some(Vec2::new(7., 7.));
+/// A collider for the thin bottom of a tile, like a trunk or a fence rail.
+const LOW: Option<Vec2> = Some(Vec2::new(6., 3.));
+
+static OMBU: PropDefinition = PropDefinition {
+    name: "ombú",
+    parts: &[part(0, 0, 32, LOW), part(0, 1, 21, None)],
+    breakable: false,
+};
+
+static FENCE: PropDefinition = PropDefinition {
+    name: "fence",
+    parts: &[
+        part(-1, 0, 33, LOW),
+        part(0, 0, 34, LOW),
+        part(1, 0, 35, LOW),
+    ],
+    breakable: true,
+};
+
+static ESTANCIA: PropDefinition = PropDefinition {
+    name: "estancia",
+    parts: &[
+        part(-1, 0, 69, BLOCK),
+        part(0, 0, 68, BLOCK),
+        part(1, 0, 70, BLOCK),
+        part(2, 0, 37, LOW),
+    ],
+    breakable: false,
+};
+
+static WELL: PropDefinition = PropDefinition {
+    name: "well",
+    parts: &[part(0, 0, 66, BLOCK)],
+    breakable: false,
+};
+
+static CARCASS: PropDefinition = PropDefinition {
+    name: "cattle carcass",
+    parts: &[part(0, 0, 20, LOW), part(1, 0, 25, None)],
+    breakable: true,
+};
+
+static PULPERIA: PropDefinition = PropDefinition {
+    name: "pulpería",
+    parts: &[
+        part(-1, 0, 82, LOW),
+        part(0, 0, 68, BLOCK),
+        PropPart {
+            supplies: Some(Supplies { health: 50 }),
+            ..part(1, 0, 79, BLOCK)
+        },
+    ],
+    breakable: false,
+};
+
+/// A prop placed in the world.
+#[derive(Clone, Copy, Debug)]
+pub struct PropPlacement {
+    pub kind: PropKind,
+    /// Grid position of the prop's anchor tile.
+    pub anchor: IVec2,
+}
+
+/// Side, in chunks, of the square regions that each get one pulpería.
+const POI_REGION: i32 = 6;
+/// Chunks at the edge of a region a pulpería is never placed in, so two of
+/// them are always at least `2 * POI_MARGIN + 1` chunks apart.
+const POI_MARGIN: i32 = 1;
+
+/// Whether the prop fits at `anchor` in the chunk at `chunk_pos`: all of it
+/// inside the chunk and clear of `taken` tiles, and its solid parts on
+/// walkable ground away from where the gaucho starts.
+fn fits(
+    terrain: &Terrain,
+    chunk_pos: IVec2,
+    kind: PropKind,
+    anchor: IVec2,
+    taken: &[IVec2],
+) -> bool {
+    let definition = kind.definition();
+    definition.parts.iter().all(|part| {
+        let tile = anchor + part.offset;
+        tile_to_chunk(tile) == chunk_pos && !taken.contains(&tile)
+    }) && definition
+        .solid_tiles(anchor)
+        .all(|tile| tile.abs().max_element() > SPAWN_CLEARING && !terrain.is_solid(tile))
+}
+
+/// Where the pulpería of the region at `region` stands, if there's room for
+/// one anywhere in it.
+fn pulperia_site(terrain: &Terrain, seed: &WorldSeed, region: IVec2) -> Option<IVec2> {
+    let mut random = seed.rng(RngStream::PointOfInterest(region));
+    let span = POI_MARGIN..POI_REGION - POI_MARGIN;
+    let mut chunks: Vec<IVec2> = span
+        .clone()
+        .flat_map(|y| {
+            span.clone()
+                .map(move |x| region * POI_REGION + IVec2::new(x, y))
+        })
+        .collect();
+    chunks.shuffle(&mut random);
+    chunks.into_iter().find_map(|chunk_pos| {
+        let mut tiles: Vec<IVec2> = chunk_tiles(chunk_pos).collect();
+        tiles.shuffle(&mut random);
+        tiles
+            .into_iter()
+            .find(|&tile| fits(terrain, chunk_pos, PropKind::Pulperia, tile, &[]))
+    })
+}
+
+/// Works out the props of the chunk at `chunk_pos`, by how dense each kind is
+/// in the biome of each tile. The same seed always gives the same props.
+pub fn generate_props(terrain: &Terrain, seed: &WorldSeed, chunk_pos: IVec2) -> Vec<PropPlacement> {
+    let mut props = Vec::new();
+    // Tiles already covered by a prop.
+    let mut taken = Vec::new();
+    let mut place = |kind: PropKind, anchor: IVec2, taken: &mut Vec<IVec2>| {
+        taken.extend(
+            kind.definition()
+                .parts
+                .iter()
+                .map(|part| anchor + part.offset),
+        );
+        props.push(PropPlacement { kind, anchor });
+    };
+
+    let region = IVec2::new(
+        chunk_pos.x.div_euclid(POI_REGION),
+        chunk_pos.y.div_euclid(POI_REGION),
+    );
+    let in_region = chunk_pos - region * POI_REGION;
+    let may_hold_pulperia =
+        in_region.min_element() >= POI_MARGIN && in_region.max_element() < POI_REGION - POI_MARGIN;
+    if may_hold_pulperia {
+        if let Some(anchor) = pulperia_site(terrain, seed, region) {
+            if tile_to_chunk(anchor) == chunk_pos {
+                place(PropKind::Pulperia, anchor, &mut taken);
+            }
+        }
+    }
+
+    let mut random = seed.rng(RngStream::Props(chunk_pos));
+    for tile in chunk_tiles(chunk_pos) {
+        for &(kind, density) in terrain.biome(tile).definition().props {
+            if random.gen_bool(density) && fits(terrain, chunk_pos, kind, tile, &taken) {
+                place(kind, tile, &mut taken);
+                break;
+            }
+        }
+    }
+    props
+}
@@ -1,17 +1,202 @@
 use bevy::prelude::*;
-use bevy::utils::HashSet;
+use bevy::utils::{Duration, HashMap, HashSet};
 use noise::NoiseFn;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::pickups::SavedPickup;
+
+/// Environment variable read for the world seed when `--seed` isn't passed.
+const SEED_ENV_VAR: &str = "GVZ_SEED";
 
 #[derive(Resource, Deref)]
 pub struct Noise(pub Box<dyn NoiseFn<f64, 2> + Send + Sync>);
 
-#[derive(Default, Debug, Resource)]
+/// Seed every procedural system derives its randomness from, so two runs
+/// with the same seed produce the same map and the same waves.
+#[derive(Resource, Clone, Copy, Debug, Deref)]
+pub struct WorldSeed(pub u64);
+
+/// Independent random streams derived from the [`WorldSeed`]. Each consumer
+/// gets its own stream so drawing numbers in one doesn't shift the others.
+#[derive(Clone, Copy, Debug)]
+pub enum RngStream {
+    Chunk(IVec2),
+    Props(IVec2),
+    /// The region of chunks a point of interest is placed in.
+    PointOfInterest(IVec2),
+    Waves,
+    Combat,
+    Upgrades,
+    Drops,
+}
+
+impl RngStream {
+    fn salt(&self) -> u64 {
+        match self {
+            RngStream::Chunk(pos) => splitmix64(pack(*pos) ^ 0x43_48_55_4E_4B),
+            RngStream::Props(pos) => splitmix64(pack(*pos) ^ 0x50_52_4F_50_53),
+            RngStream::PointOfInterest(pos) => splitmix64(pack(*pos) ^ 0x50_4F_49),
+            RngStream::Waves => splitmix64(0x57_41_56_45),
+            RngStream::Combat => splitmix64(0x43_4F_4D_42_41_54),
+            RngStream::Upgrades => splitmix64(0x55_50_47_52_41_44_45),
+            RngStream::Drops => splitmix64(0x44_52_4F_50_53),
+        }
+    }
+}
+
+impl WorldSeed {
+    /// Reads the seed from `--seed <n>`, then from `GVZ_SEED`, falling back to
+    /// a random one only when neither is given. A seed that isn't a number
+    /// quits with the reason, rather than quietly playing another world.
+    pub fn from_args_or_env() -> Self {
+        let mut args = std::env::args().skip_while(|arg| arg != "--seed");
+        let (source, seed) = if args.next().is_some() {
+            ("--seed", args.next().unwrap_or_default())
+        } else if let Ok(seed) = std::env::var(SEED_ENV_VAR) {
+            (SEED_ENV_VAR, seed)
+        } else {
+            return WorldSeed(rand::random());
+        };
+        match seed.parse() {
+            Ok(seed) => WorldSeed(seed),
+            Err(error) => {
+                eprintln!("Can't use {seed:?} from {source} as a seed: {error}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    pub fn noise_seed(&self) -> u32 {
+        (self.0 ^ (self.0 >> 32)) as u32
+    }
+
+    pub fn rng(&self, stream: RngStream) -> StdRng {
+        StdRng::seed_from_u64(splitmix64(self.0 ^ stream.salt()))
+    }
+}
+
+fn pack(pos: IVec2) -> u64 {
+    ((pos.x as u32 as u64) << 32) | pos.y as u32 as u64
+}
+
+fn splitmix64(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[derive(Resource, Deref, DerefMut)]
+pub struct WaveRng(pub StdRng);
+
+#[derive(Resource, Deref, DerefMut)]
+pub struct CombatRng(pub StdRng);
+
+#[derive(Resource, Deref, DerefMut)]
+pub struct UpgradeRng(pub StdRng);
+
+#[derive(Resource, Deref, DerefMut)]
+pub struct DropRng(pub StdRng);
+
+/// What changed in a chunk since it was generated. Chunks are regenerated
+/// from the seed whenever they spawn again, and their delta reapplied.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkDelta {
+    /// Indices, among the props generated for the chunk, of the ones broken.
+    pub destroyed_props: HashSet<usize>,
+    /// Indices of the props whose supplies were taken.
+    pub looted_props: HashSet<usize>,
+    /// Blood stains, relative to the chunk's origin.
+    pub blood_stains: Vec<Vec2>,
+    /// Pickups left on the ground when the chunk was unloaded.
+    pub pickups: Vec<SavedPickup>,
+}
+
+/// A chunk spawned in the world.
+#[derive(Debug)]
+pub struct LoadedChunk {
+    pub tilemap: Entity,
+    /// Tiles nothing walks through, be it for the ground or for a prop.
+    pub solid: Vec<IVec2>,
+}
+
+#[derive(Default, Debug, Resource, Serialize, Deserialize)]
 pub struct ChunkManager {
-    pub spawned_chunks: HashSet<IVec2>,
+    #[serde(skip)]
+    pub spawned_chunks: HashMap<IVec2, LoadedChunk>,
+    /// Every chunk changed this run, loaded or not.
+    pub deltas: HashMap<IVec2, ChunkDelta>,
+    /// Tiles of the loaded chunks that stopped being solid, until the flow
+    /// field lets paths through them.
+    #[serde(skip)]
+    pub opened_tiles: Vec<IVec2>,
+}
+
+impl ChunkManager {
+    pub fn delta_mut(&mut self, chunk_pos: IVec2) -> &mut ChunkDelta {
+        self.deltas.entry(chunk_pos).or_default()
+    }
 }
 
 #[derive(Resource)]
 pub struct BulletTimer(pub Timer);
 
-#[derive(Resource)]
-pub struct WaveSpawnTimer(pub Timer);
+/// Zombies bucketed by position into square cells, rebuilt every frame, so
+/// that finding the ones near a point doesn't mean looking at all of them.
+#[derive(Default, Resource)]
+pub struct SpatialHash {
+    cells: HashMap<IVec2, Vec<(Entity, Vec2)>>,
+}
+
+impl SpatialHash {
+    /// Side of a cell in pixels. Queries are cheapest with radii below it.
+    pub const CELL_SIZE: f32 = 32.;
+
+    fn cell(position: Vec2) -> IVec2 {
+        (position / Self::CELL_SIZE).floor().as_ivec2()
+    }
+
+    pub fn clear(&mut self) {
+        // Keep the buckets around, most of them are needed again next frame.
+        for bucket in self.cells.values_mut() {
+            bucket.clear();
+        }
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: Vec2) {
+        self.cells
+            .entry(Self::cell(position))
+            .or_default()
+            .push((entity, position));
+    }
+
+    /// Entities within `radius` of `position`.
+    pub fn neighbors(
+        &self,
+        position: Vec2,
+        radius: f32,
+    ) -> impl Iterator<Item = (Entity, Vec2)> + '_ {
+        let min = Self::cell(position - radius);
+        let max = Self::cell(position + radius);
+        (min.y..=max.y)
+            .flat_map(move |y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(move |(_, other)| other.distance_squared(position) <= radius * radius)
+    }
+}
+
+/// Running totals for the current session, used for the end of run report.
+#[derive(Default, Debug, Clone, Resource, Serialize, Deserialize)]
+pub struct GameStats {
+    pub kills: usize,
+    pub score: u32,
+    /// Pesos picked up.
+    pub coins: u32,
+    pub waves_cleared: u32,
+    pub damage_taken: usize,
+    pub survival_time: Duration,
+}
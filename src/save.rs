@@ -0,0 +1,290 @@
+//! Saving the session to a RON file and restoring it later.
+//!
+//! A save holds what can't be generated again from the [`WorldSeed`]: the
+//! gaucho, the live zombies, the wave director, the run's stats and the
+//! changes made to chunks. Saves carry a [`SAVE_VERSION`]; ones written by
+//! another version of the game are refused instead of half loaded.
+
+use std::fmt;
+use std::fs;
+use std::io;
+
+use bevy::prelude::*;
+use bevy::utils::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::archetypes::ZombieKind;
+use crate::components::Health;
+use crate::pickups::PowerUps;
+use crate::progression::{Experience, Upgrades};
+use crate::resources::{ChunkManager, GameStats, WorldSeed};
+use crate::status::StatusEffects;
+use crate::waves::{WaveDirector, WavePhase, WaveTable};
+use crate::weapons::{Arsenal, WeaponKind};
+
+/// Bumped whenever the save format changes in a way older saves can't be
+/// read with.
+pub const SAVE_VERSION: u32 = 5;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SaveGame {
+    pub version: u32,
+    pub seed: u64,
+    pub gaucho: SavedGaucho,
+    pub zombies: Vec<SavedZombie>,
+    pub waves: SavedWaves,
+    pub stats: GameStats,
+    pub chunks: ChunkManager,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SavedGaucho {
+    pub position: Vec2,
+    pub health: usize,
+    pub max_health: usize,
+    pub weapons: Vec<WeaponKind>,
+    pub equipped: usize,
+    pub experience: Experience,
+    pub upgrades: Upgrades,
+    pub power_ups: PowerUps,
+    pub statuses: StatusEffects,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SavedZombie {
+    pub kind: ZombieKind,
+    pub position: Vec2,
+    pub health: usize,
+    /// As scaled for the wave it came in, which may not be the saved one.
+    pub max_health: usize,
+    pub statuses: StatusEffects,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SavedWaves {
+    pub wave: u32,
+    pub phase: SavedPhase,
+}
+
+/// A [`WavePhase`], with its timers down to the seconds they have run for.
+#[derive(Serialize, Deserialize, Debug)]
+pub enum SavedPhase {
+    Resting { duration: f32, elapsed: f32 },
+    Spawning { remaining: usize, elapsed: f32 },
+    Fighting,
+}
+
+/// Just enough of a save to tell which version wrote it.
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Format(String),
+    /// Written by another version of the game.
+    Version {
+        found: u32,
+    },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(error) => write!(f, "{error}"),
+            SaveError::Format(error) => write!(f, "not a valid save: {error}"),
+            SaveError::Version { found } => write!(
+                f,
+                "saved by another version of the game (save version {found}, \
+                 this game reads version {SAVE_VERSION})"
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(error: io::Error) -> Self {
+        SaveError::Io(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for SaveError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        SaveError::Format(error.to_string())
+    }
+}
+
+impl From<ron::Error> for SaveError {
+    fn from(error: ron::Error) -> Self {
+        SaveError::Format(error.to_string())
+    }
+}
+
+impl SaveGame {
+    pub const PATH: &'static str = "savegame.ron";
+
+    pub fn write(&self, path: &str) -> Result<(), SaveError> {
+        let text = ron::ser::to_string_pretty(self, default())?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// Reads the save at `path`, checking its version before anything else.
+    pub fn read(path: &str) -> Result<Self, SaveError> {
+        let text = fs::read_to_string(path)?;
+        let header: SaveHeader = ron::from_str(&text)?;
+        if header.version != SAVE_VERSION {
+            return Err(SaveError::Version {
+                found: header.version,
+            });
+        }
+        let save: Self = ron::from_str(&text)?;
+        if save.gaucho.weapons.is_empty() {
+            return Err(SaveError::Format("the gaucho has no weapons".to_string()));
+        }
+        Ok(save)
+    }
+
+    pub fn seed(&self) -> WorldSeed {
+        WorldSeed(self.seed)
+    }
+}
+
+impl SavedGaucho {
+    pub fn health(&self) -> Health {
+        Health {
+            current: self.health,
+            max: self.max_health,
+        }
+    }
+
+    pub fn arsenal(&self) -> Arsenal {
+        let mut arsenal = Arsenal::new(self.weapons.clone());
+        arsenal.equip(self.equipped);
+        arsenal
+    }
+}
+
+impl From<&WaveDirector> for SavedWaves {
+    fn from(director: &WaveDirector) -> Self {
+        let phase = match &director.phase {
+            WavePhase::Resting(timer) => SavedPhase::Resting {
+                duration: timer.duration().as_secs_f32(),
+                elapsed: timer.elapsed_secs(),
+            },
+            WavePhase::Spawning { remaining, timer } => SavedPhase::Spawning {
+                remaining: *remaining,
+                elapsed: timer.elapsed_secs(),
+            },
+            WavePhase::Fighting => SavedPhase::Fighting,
+        };
+        Self {
+            wave: director.wave,
+            phase,
+        }
+    }
+}
+
+impl SavedWaves {
+    /// The [`WaveDirector`] as it was, its wave defined by `table`.
+    pub fn director(&self, table: &WaveTable) -> WaveDirector {
+        let definition = table.wave(self.wave);
+        let phase = match self.phase {
+            SavedPhase::Resting { duration, elapsed } => {
+                let mut timer = Timer::from_seconds(duration, TimerMode::Once);
+                timer.set_elapsed(Duration::from_secs_f32(elapsed));
+                WavePhase::Resting(timer)
+            }
+            SavedPhase::Spawning { remaining, elapsed } => {
+                let mut timer =
+                    Timer::from_seconds(definition.spawn_interval, TimerMode::Repeating);
+                timer.set_elapsed(Duration::from_secs_f32(elapsed));
+                WavePhase::Spawning { remaining, timer }
+            }
+            SavedPhase::Fighting => WavePhase::Fighting,
+        };
+        WaveDirector {
+            wave: self.wave,
+            definition,
+            phase,
+        }
+    }
+}
+
+/// A save waiting to be restored when the next session starts, picked with
+/// "Continue" in the main menu.
+#[derive(Resource, Default)]
+pub struct PendingLoad(pub Option<SaveGame>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::StatusKind;
+
+    fn save(version: u32) -> SaveGame {
+        SaveGame {
+            version,
+            seed: 42,
+            gaucho: SavedGaucho {
+                position: Vec2::new(10., -20.),
+                health: 80,
+                max_health: 100,
+                weapons: vec![WeaponKind::Facon],
+                equipped: 0,
+                experience: default(),
+                upgrades: default(),
+                power_ups: default(),
+                statuses: default(),
+            },
+            zombies: vec![SavedZombie {
+                kind: ZombieKind::Walker,
+                position: Vec2::new(50., 50.),
+                health: 30,
+                max_health: 40,
+                statuses: default(),
+            }],
+            waves: SavedWaves {
+                wave: 2,
+                phase: SavedPhase::Fighting,
+            },
+            stats: default(),
+            chunks: default(),
+        }
+    }
+
+    /// A file of its own in the temporary folder, since tests run at once.
+    fn path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("gauchos_vs_zombies_{name}.ron"));
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn reads_back_what_it_wrote() {
+        let path = path("save_roundtrip");
+        let mut save = save(SAVE_VERSION);
+        save.zombies[0].statuses.apply(StatusKind::Burn, None);
+        save.write(&path).unwrap();
+        let read = SaveGame::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(read.seed, 42);
+        assert_eq!(read.gaucho.health, 80);
+        assert_eq!(read.zombies.len(), 1);
+        assert_eq!(read.zombies[0].max_health, 40);
+        assert_eq!(read.zombies[0].statuses.effects[0].kind, StatusKind::Burn);
+        assert_eq!(read.waves.wave, 2);
+    }
+
+    #[test]
+    fn refuses_saves_of_another_version() {
+        let path = path("save_old_version");
+        save(SAVE_VERSION - 1).write(&path).unwrap();
+        let read = SaveGame::read(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(
+            matches!(read, Err(SaveError::Version { found }) if found == SAVE_VERSION - 1),
+            "{read:?}"
+        );
+    }
+}
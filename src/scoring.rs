@@ -0,0 +1,134 @@
+use std::fs;
+use std::io::ErrorKind;
+
+use bevy::prelude::*;
+use bevy::utils::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::archetypes::ZombieKind;
+use crate::weapons::WeaponKind;
+
+/// Seconds a combo lasts after its last kill.
+pub const COMBO_WINDOW: f32 = 2.5;
+/// Kills in a row it takes for the multiplier to grow by one.
+pub const KILLS_PER_MULTIPLIER: u32 = 5;
+pub const MAX_MULTIPLIER: u32 = 5;
+/// Runs kept in the high-score table.
+pub const MAX_HIGH_SCORES: usize = 10;
+
+/// Sent when the gaucho kills a zombie.
+pub struct ZombieKilled {
+    pub weapon: WeaponKind,
+    pub kind: ZombieKind,
+    pub position: Vec2,
+}
+
+/// Kills made in quick succession, each within [`COMBO_WINDOW`] seconds of
+/// the one before.
+#[derive(Resource)]
+pub struct Combo {
+    pub kills: u32,
+    pub timer: Timer,
+}
+
+impl Default for Combo {
+    fn default() -> Self {
+        Self {
+            kills: 0,
+            timer: Timer::from_seconds(COMBO_WINDOW, TimerMode::Once),
+        }
+    }
+}
+
+impl Combo {
+    /// What the points of the next kill are multiplied by.
+    pub fn multiplier(&self) -> u32 {
+        (1 + self.kills / KILLS_PER_MULTIPLIER).min(MAX_MULTIPLIER)
+    }
+
+    /// Counts a kill, returning the points it's worth.
+    pub fn kill(&mut self, points: u32) -> u32 {
+        let points = points * self.multiplier();
+        self.kills += 1;
+        self.timer.reset();
+        points
+    }
+
+    /// Runs the clock of the combo, returning whether it just ran out.
+    pub fn tick(&mut self, delta: Duration) -> bool {
+        if self.kills > 0 && self.timer.tick(delta).finished() {
+            self.kills = 0;
+            return true;
+        }
+        false
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HighScore {
+    pub score: u32,
+    pub kills: usize,
+    pub waves_cleared: u32,
+    pub survival_time: Duration,
+    pub seed: u64,
+}
+
+/// The best runs played on this machine, best first.
+#[derive(Resource, Serialize, Deserialize, Default, Debug)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+    /// Where the table is kept, if anywhere. The headless simulation keeps
+    /// none.
+    #[serde(skip)]
+    pub file: Option<String>,
+    /// Where the run that just ended placed, if it made the table.
+    #[serde(skip)]
+    pub latest: Option<usize>,
+}
+
+impl HighScores {
+    pub const PATH: &'static str = "highscores.ron";
+
+    /// Reads the table kept at `path`, starting an empty one when there's
+    /// none yet or it can't be read.
+    pub fn load(path: &str) -> Self {
+        let mut scores = match fs::read_to_string(path) {
+            Ok(text) => ron::from_str(&text).unwrap_or_else(|error| {
+                warn!("Ignoring the high scores in {path}: {error}");
+                Self::default()
+            }),
+            Err(error) if error.kind() == ErrorKind::NotFound => Self::default(),
+            Err(error) => {
+                warn!("Couldn't read the high scores in {path}: {error}");
+                Self::default()
+            }
+        };
+        scores.file = Some(path.to_string());
+        scores
+    }
+
+    /// Places a run in the table, behind those that scored as much, and
+    /// keeps the table on disk.
+    pub fn record(&mut self, entry: HighScore) {
+        let rank = self
+            .entries
+            .iter()
+            .position(|other| other.score < entry.score)
+            .unwrap_or(self.entries.len());
+        self.latest = (rank < MAX_HIGH_SCORES).then_some(rank);
+        if self.latest.is_none() {
+            return;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_HIGH_SCORES);
+        let Some(path) = &self.file else {
+            return;
+        };
+        let written = ron::ser::to_string_pretty(self, default())
+            .map_err(|error| error.to_string())
+            .and_then(|text| fs::write(path, text).map_err(|error| error.to_string()));
+        if let Err(error) = written {
+            warn!("Couldn't write the high scores to {path}: {error}");
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::fs;
+use std::io::ErrorKind;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Step the settings screen moves the volume by.
+pub const VOLUME_STEP: f32 = 0.1;
+
+/// What the player picks in the settings screen. It's kept in
+/// [`GameSettings::PATH`] so it outlives the game.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct GameSettings {
+    /// From 0 to 1, for every sound the game plays.
+    pub volume: f32,
+    pub fullscreen: bool,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            fullscreen: false,
+        }
+    }
+}
+
+impl GameSettings {
+    pub const PATH: &'static str = "settings.ron";
+
+    /// Reads the settings from `path`, falling back to the defaults when
+    /// there are none yet or they can't be read.
+    pub fn load(path: &str) -> Self {
+        match fs::read_to_string(path) {
+            Ok(text) => ron::from_str(&text).unwrap_or_else(|error| {
+                warn!("Ignoring the settings in {path}: {error}");
+                Self::default()
+            }),
+            Err(error) if error.kind() == ErrorKind::NotFound => Self::default(),
+            Err(error) => {
+                warn!("Couldn't read the settings in {path}: {error}");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn write(&self, path: &str) {
+        let written = ron::ser::to_string_pretty(self, default())
+            .map_err(|error| error.to_string())
+            .and_then(|text| fs::write(path, text).map_err(|error| error.to_string()));
+        if let Err(error) = written {
+            warn!("Couldn't write the settings to {path}: {error}");
+        }
+    }
+
+    /// Turns the volume up or down by `steps` of [`VOLUME_STEP`].
+    pub fn change_volume(&mut self, steps: f32) {
+        // Rounded so repeated steps land on round numbers.
+        let volume = ((self.volume + steps * VOLUME_STEP) / VOLUME_STEP).round() * VOLUME_STEP;
+        self.volume = volume.clamp(0., 1.);
+    }
+
+    /// How a sound effect plays at the current volume.
+    pub fn sound(&self) -> PlaybackSettings {
+        PlaybackSettings::ONCE.with_volume(self.volume)
+    }
+}
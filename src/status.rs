@@ -0,0 +1,238 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::weapons::WeaponKind;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum StatusKind {
+    Bleed,
+    Burn,
+    Slow,
+    Stun,
+}
+
+/// What happens when a status is applied to someone who already has it.
+#[derive(Clone, Copy, Debug)]
+pub enum Stacking {
+    /// Each application adds a stack, up to `max`, and starts the clock
+    /// over. Every stack counts.
+    Intensity { max: u32 },
+    /// Never more than one stack; the clock is topped back up.
+    Refresh,
+}
+
+pub struct StatusDefinition {
+    /// Seconds it lasts once applied.
+    pub duration: f32,
+    /// Health lost per second, per stack.
+    pub damage_per_second: f32,
+    /// Fraction of the speed it takes away.
+    pub slow: f32,
+    /// Stops the one it's on from moving or attacking.
+    pub stuns: bool,
+    /// Multiplied into the sprite's color while it lasts.
+    pub tint: Color,
+    pub stacking: Stacking,
+}
+
+impl StatusKind {
+    pub fn definition(&self) -> &'static StatusDefinition {
+        match self {
+            StatusKind::Bleed => &BLEED,
+            StatusKind::Burn => &BURN,
+            StatusKind::Slow => &SLOW,
+            StatusKind::Stun => &STUN,
+        }
+    }
+}
+
+static BLEED: StatusDefinition = StatusDefinition {
+    duration: 4.,
+    damage_per_second: 3.,
+    slow: 0.,
+    stuns: false,
+    tint: Color::rgb(1., 0.55, 0.55),
+    stacking: Stacking::Intensity { max: 5 },
+};
+
+static BURN: StatusDefinition = StatusDefinition {
+    duration: 3.,
+    damage_per_second: 8.,
+    slow: 0.,
+    stuns: false,
+    tint: Color::rgb(1., 0.6, 0.25),
+    stacking: Stacking::Refresh,
+};
+
+static SLOW: StatusDefinition = StatusDefinition {
+    duration: 2.,
+    damage_per_second: 0.,
+    slow: 0.4,
+    stuns: false,
+    tint: Color::rgb(0.55, 0.7, 1.),
+    stacking: Stacking::Refresh,
+};
+
+static STUN: StatusDefinition = StatusDefinition {
+    duration: 0.8,
+    damage_per_second: 0.,
+    slow: 0.,
+    stuns: true,
+    tint: Color::rgb(1., 1., 0.45),
+    stacking: Stacking::Refresh,
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub stacks: u32,
+    /// Seconds left before it wears off.
+    pub remaining: f32,
+    /// The gaucho's weapon that applied it last, if it was one, so a zombie
+    /// it kills counts as his.
+    pub source: Option<WeaponKind>,
+}
+
+/// The timed effects on a gaucho or zombie, on top of the instant
+/// [`Damage`](crate::components::Damage) of hits.
+#[derive(Component, Serialize, Deserialize, Clone, Default, Debug)]
+pub struct StatusEffects {
+    pub effects: Vec<StatusEffect>,
+    /// Damage over time not dealt yet, for less than a whole point.
+    pub owed: f32,
+}
+
+impl StatusEffects {
+    pub fn apply(&mut self, kind: StatusKind, source: Option<WeaponKind>) {
+        let definition = kind.definition();
+        let Some(effect) = self.effects.iter_mut().find(|effect| effect.kind == kind) else {
+            self.effects.push(StatusEffect {
+                kind,
+                stacks: 1,
+                remaining: definition.duration,
+                source,
+            });
+            return;
+        };
+        if let Stacking::Intensity { max } = definition.stacking {
+            effect.stacks = (effect.stacks + 1).min(max);
+        }
+        effect.remaining = effect.remaining.max(definition.duration);
+        effect.source = source.or(effect.source);
+    }
+
+    /// Runs the clocks `seconds` forward, returning the whole points of
+    /// damage over time dealt meanwhile.
+    pub fn tick(&mut self, seconds: f32) -> usize {
+        for effect in &self.effects {
+            let per_second = effect.kind.definition().damage_per_second * effect.stacks as f32;
+            self.owed += per_second * seconds.min(effect.remaining);
+        }
+        for effect in &mut self.effects {
+            effect.remaining -= seconds;
+        }
+        self.effects.retain(|effect| effect.remaining > 0.);
+        if self.effects.is_empty() {
+            // Nothing's left to round it up.
+            let owed = self.owed.round();
+            self.owed = 0.;
+            return owed as usize;
+        }
+        let dealt = self.owed.floor();
+        self.owed -= dealt;
+        dealt as usize
+    }
+
+    pub fn stunned(&self) -> bool {
+        self.effects
+            .iter()
+            .any(|effect| effect.kind.definition().stuns)
+    }
+
+    /// What speed is multiplied by; the strongest slow wins.
+    pub fn speed_multiplier(&self) -> f32 {
+        let slow = self
+            .effects
+            .iter()
+            .map(|effect| effect.kind.definition().slow)
+            .fold(0., f32::max);
+        1. - slow
+    }
+
+    /// The weapon that gets the kill if damage over time finishes the one
+    /// it's on.
+    pub fn killer(&self) -> Option<WeaponKind> {
+        self.effects
+            .iter()
+            .filter(|effect| effect.kind.definition().damage_per_second > 0.)
+            .find_map(|effect| effect.source)
+    }
+
+    /// `base` tinted by every active effect.
+    pub fn tint(&self, base: Color) -> Color {
+        self.effects.iter().fold(base, |color, effect| {
+            let tint = effect.kind.definition().tint;
+            Color::rgba(
+                color.r() * tint.r(),
+                color.g() * tint.g(),
+                color.b() * tint.b(),
+                color.a(),
+            )
+        })
+    }
+}
+
+/// Ground that applies `status` to whoever stands within `radius` pixels,
+/// until its `lifetime` runs out.
+#[derive(Component)]
+pub struct Hazard {
+    pub status: StatusKind,
+    pub radius: f32,
+    pub lifetime: Timer,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intensity_stacks_up_to_its_max() {
+        let mut statuses = StatusEffects::default();
+        for _ in 0..7 {
+            statuses.apply(StatusKind::Bleed, None);
+        }
+        assert_eq!(statuses.effects.len(), 1);
+        assert_eq!(statuses.effects[0].stacks, 5);
+    }
+
+    #[test]
+    fn refresh_only_starts_the_clock_over() {
+        let mut statuses = StatusEffects::default();
+        statuses.apply(StatusKind::Burn, None);
+        statuses.tick(2.);
+        statuses.apply(StatusKind::Burn, Some(WeaponKind::Trabuco));
+        let burn = statuses.effects[0];
+        assert_eq!(statuses.effects.len(), 1);
+        assert_eq!(burn.stacks, 1);
+        assert_eq!(burn.remaining, BURN.duration);
+        assert_eq!(burn.source, Some(WeaponKind::Trabuco));
+    }
+
+    #[test]
+    fn slows_and_stuns_last_as_long_as_their_effects() {
+        let mut statuses = StatusEffects::default();
+        assert_eq!(statuses.speed_multiplier(), 1.);
+        assert!(!statuses.stunned());
+        statuses.apply(StatusKind::Slow, None);
+        statuses.apply(StatusKind::Stun, None);
+        assert_eq!(statuses.speed_multiplier(), 1. - SLOW.slow);
+        assert!(statuses.stunned());
+        // The stun wears off first.
+        statuses.tick(1.);
+        assert_eq!(statuses.speed_multiplier(), 1. - SLOW.slow);
+        assert!(!statuses.stunned());
+        statuses.tick(1.5);
+        assert_eq!(statuses.speed_multiplier(), 1.);
+        assert!(statuses.effects.is_empty());
+    }
+}
@@ -2,10 +2,13 @@ use bevy::prelude::*;
 
 use crate::Gaucho;
 
+/// Keeps the camera over the gaucho, at its own depth so the y-sorted
+/// sprites stay in view.
 pub fn camera_movement(
     gaucho_transform: Query<&Transform, With<Gaucho>>,
     mut camera_position: Query<&mut Transform, (With<Camera>, Without<Gaucho>)>,
 ) {
     let mut tr = camera_position.single_mut();
-    tr.translation = gaucho_transform.single().translation;
+    let gaucho_translation = gaucho_transform.single().translation;
+    tr.translation = gaucho_translation.truncate().extend(tr.translation.z);
 }
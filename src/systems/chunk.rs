@@ -1,29 +1,138 @@
 use crate::assets::ImageAssets;
-use crate::resources::{ChunkManager, Noise};
+use crate::pickups::Pickup;
+use crate::props::{generate_props, PropPlacement};
+use crate::resources::{ChunkDelta, ChunkManager, LoadedChunk, WorldSeed};
+use crate::systems::pickups::{spawn_pickup, store_pickups};
+use crate::systems::props::spawn_prop;
+use crate::terrain::{generate_chunk, ChunkTerrain, Terrain};
 use bevy::math::Vec3Swizzles;
 use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+use bevy::utils::HashMap;
 use bevy_ecs_tilemap::prelude::TilePos;
 use bevy_ecs_tilemap::prelude::{TilemapId, TilemapTexture, TilemapTileSize};
 use bevy_ecs_tilemap::tiles::{TileBundle, TileStorage, TileTextureIndex};
 use bevy_ecs_tilemap::TilemapBundle;
-use rand::{thread_rng, Rng};
+use bevy_rapier2d::prelude::*;
+use futures_lite::future;
 
 const TILE_SIZE: TilemapTileSize = TilemapTileSize { x: 16.0, y: 16.0 };
 // For this example, don't choose too large a chunk size.
-const CHUNK_SIZE: UVec2 = UVec2 { x: 8, y: 8 };
+pub const CHUNK_SIZE: UVec2 = UVec2 { x: 8, y: 8 };
 // Render chunk sizes are set to 4 render chunks per user specified chunk.
 pub const RENDER_CHUNK_SIZE: UVec2 = UVec2 {
     x: CHUNK_SIZE.x * 2,
     y: CHUNK_SIZE.y * 2,
 };
 
+/// Grid position of the tile under `world_pos`, counting from the tile at the
+/// origin.
+pub fn world_to_tile(world_pos: Vec2) -> IVec2 {
+    let tile_size = Vec2::new(TILE_SIZE.x, TILE_SIZE.y);
+    // Tiles are centered on their grid position.
+    ((world_pos + tile_size / 2.) / tile_size)
+        .floor()
+        .as_ivec2()
+}
+
+/// Center of the tile at grid position `tile`.
+pub fn tile_to_world(tile: IVec2) -> Vec2 {
+    tile.as_vec2() * Vec2::new(TILE_SIZE.x, TILE_SIZE.y)
+}
+
+/// The chunk a tile belongs to.
+pub fn tile_to_chunk(tile: IVec2) -> IVec2 {
+    let chunk_size = CHUNK_SIZE.as_ivec2();
+    IVec2::new(
+        tile.x.div_euclid(chunk_size.x),
+        tile.y.div_euclid(chunk_size.y),
+    )
+}
+
+/// World position of the center of a chunk's first tile, where its tilemap
+/// sits.
+pub fn chunk_origin(chunk_pos: IVec2) -> Vec2 {
+    tile_to_world(chunk_pos * CHUNK_SIZE.as_ivec2())
+}
+
+/// Grid positions of the tiles of a chunk.
+pub fn chunk_tiles(chunk_pos: IVec2) -> impl Iterator<Item = IVec2> {
+    let origin = chunk_pos * CHUNK_SIZE.as_ivec2();
+    (0..CHUNK_SIZE.y as i32)
+        .flat_map(|y| (0..CHUNK_SIZE.x as i32).map(move |x| IVec2::new(x, y)))
+        .map(move |tile| origin + tile)
+}
+
+/// Most blood stains a chunk keeps; later ones aren't recorded.
+const MAX_BLOOD_STAINS: usize = 32;
+
+/// A chunk's tiles and props, as generated from the seed.
+pub struct GeneratedChunk {
+    ground: ChunkTerrain,
+    props: Vec<PropPlacement>,
+}
+
+impl GeneratedChunk {
+    pub fn new(terrain: &Terrain, seed: &WorldSeed, chunk_pos: IVec2) -> Self {
+        Self {
+            ground: generate_chunk(terrain, seed, chunk_pos),
+            props: generate_props(terrain, seed, chunk_pos),
+        }
+    }
+}
+
+/// Chunks being generated on the [`AsyncComputeTaskPool`], waiting to be
+/// spawned on the main thread.
+#[derive(Resource)]
+pub struct ChunkGenerator {
+    pending: HashMap<IVec2, Task<GeneratedChunk>>,
+    /// Most chunks generated at once.
+    pub max_pending: usize,
+    /// Most generated chunks spawned per frame.
+    pub spawn_budget: usize,
+    /// Waits for the pending chunks instead of only spawning the finished
+    /// ones, so chunks come in on the same frame on every run.
+    pub wait: bool,
+}
+
+impl Default for ChunkGenerator {
+    fn default() -> Self {
+        Self {
+            pending: HashMap::default(),
+            max_pending: 16,
+            spawn_budget: 4,
+            wait: false,
+        }
+    }
+}
+
+impl ChunkGenerator {
+    /// A generator that waits for the chunks it started, for runs that must
+    /// play out the same every time.
+    pub fn waiting() -> Self {
+        Self {
+            wait: true,
+            ..default()
+        }
+    }
+
+    /// Drops the chunks still being generated.
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+/// Spawns the chunk at `chunk_pos` from its generated tiles and props, with
+/// the changes in `delta` applied.
 pub fn spawn_chunk(
     commands: &mut Commands,
     image_assets: &Res<ImageAssets>,
     chunk_pos: IVec2,
-    noise: &Res<Noise>,
-) {
-    let mut random = thread_rng();
+    generated: GeneratedChunk,
+    delta: Option<&ChunkDelta>,
+) -> LoadedChunk {
+    let GeneratedChunk { ground, props } = generated;
+    let mut solid = Vec::new();
     let tilemap_entity = commands.spawn_empty().id();
     let mut tile_storage = TileStorage::empty(CHUNK_SIZE.into());
 
@@ -31,37 +140,56 @@ pub fn spawn_chunk(
 
     for x in 0..CHUNK_SIZE.x {
         for y in 0..CHUNK_SIZE.y {
-            let noise_val = ((noise.get([
-                chunk_pos.x as f64 * CHUNK_SIZE.x as f64 * TILE_SIZE.x as f64
-                    + x as f64 * TILE_SIZE.x as f64 / 100.,
-                chunk_pos.y as f64 * CHUNK_SIZE.y as f64 * TILE_SIZE.y as f64
-                    + y as f64 * TILE_SIZE.y as f64 / 100.,
-            ]) + 1.)
-                / 2.) as f32;
             let tile_pos = TilePos { x, y };
-            let text_index = TileTextureIndex(if noise_val < 0.8 {
-                random.gen_range(0..6)
-            } else {
-                100
-            });
+            let tile = ground.get(UVec2::new(x, y));
             let tile_entity = commands
                 .spawn(TileBundle {
                     position: tile_pos,
                     tilemap_id: TilemapId(tilemap_entity),
-                    texture_index: text_index,
+                    texture_index: TileTextureIndex(tile.texture),
                     ..Default::default()
                 })
                 .id();
+            if tile.solid {
+                solid.push(chunk_pos * CHUNK_SIZE.as_ivec2() + IVec2::new(x as i32, y as i32));
+                let collider = commands
+                    .spawn(TransformBundle::from(Transform::from_xyz(
+                        x as f32 * TILE_SIZE.x,
+                        y as f32 * TILE_SIZE.y,
+                        0.,
+                    )))
+                    .insert(RigidBody::Fixed)
+                    .insert(Collider::cuboid(TILE_SIZE.x / 2., TILE_SIZE.y / 2.))
+                    .id();
+                commands.entity(tilemap_entity).add_child(collider);
+            }
             commands.entity(tilemap_entity).add_child(tile_entity);
             tile_storage.set(&tile_pos, tile_entity);
         }
     }
 
-    let transform = Transform::from_translation(Vec3::new(
-        chunk_pos.x as f32 * CHUNK_SIZE.x as f32 * TILE_SIZE.x,
-        chunk_pos.y as f32 * CHUNK_SIZE.y as f32 * TILE_SIZE.y,
-        0.0,
-    ));
+    let transform = Transform::from_translation(chunk_origin(chunk_pos).extend(0.0));
+    for (index, prop) in props.into_iter().enumerate() {
+        if matches!(delta, Some(delta) if delta.destroyed_props.contains(&index)) {
+            continue;
+        }
+        solid.extend(prop.kind.definition().solid_tiles(prop.anchor));
+        spawn_prop(
+            commands,
+            image_assets,
+            tilemap_entity,
+            chunk_pos,
+            index,
+            prop,
+            matches!(delta, Some(delta) if delta.looted_props.contains(&index)),
+        );
+    }
+    for &stain in delta.iter().flat_map(|delta| &delta.blood_stains) {
+        spawn_blood_stain(commands, tilemap_entity, stain);
+    }
+    for &pickup in delta.iter().flat_map(|delta| &delta.pickups) {
+        spawn_pickup(commands, tilemap_entity, chunk_pos, pickup);
+    }
     let texture_handle = image_assets.tiles.clone();
     commands.entity(tilemap_entity).insert(TilemapBundle {
         grid_size: TILE_SIZE.into(),
@@ -72,51 +200,318 @@ pub fn spawn_chunk(
         transform,
         ..Default::default()
     });
+    LoadedChunk {
+        tilemap: tilemap_entity,
+        solid,
+    }
+}
+
+fn spawn_blood_stain(commands: &mut Commands, tilemap: Entity, offset: Vec2) {
+    let stain = commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(0.45, 0.02, 0.02, 0.7),
+                custom_size: Some(Vec2::new(7., 4.)),
+                ..default()
+            },
+            // Over the ground, under everything standing on it.
+            transform: Transform::from_translation(offset.extend(0.5)),
+            ..default()
+        })
+        .id();
+    commands.entity(tilemap).add_child(stain);
+}
+
+/// Stains the ground at `world_pos`, and remembers it for when the chunk is
+/// spawned again.
+pub fn leave_blood_stain(
+    commands: &mut Commands,
+    chunk_manager: &mut ChunkManager,
+    world_pos: Vec2,
+) {
+    let chunk_pos = tile_to_chunk(world_to_tile(world_pos));
+    let offset = world_pos - chunk_origin(chunk_pos);
+    let stains = &mut chunk_manager.delta_mut(chunk_pos).blood_stains;
+    if stains.len() >= MAX_BLOOD_STAINS {
+        return;
+    }
+    stains.push(offset);
+    if let Some(chunk) = chunk_manager.spawned_chunks.get(&chunk_pos) {
+        spawn_blood_stain(commands, chunk.tilemap, offset);
+    }
 }
 
+/// The chunk under `camera_pos`.
 pub fn camera_pos_to_chunk_pos(camera_pos: &Vec2) -> IVec2 {
-    let camera_pos = camera_pos.as_ivec2();
-    let chunk_size: IVec2 = IVec2::new(CHUNK_SIZE.x as i32, CHUNK_SIZE.y as i32);
-    let tile_size: IVec2 = IVec2::new(TILE_SIZE.x as i32, TILE_SIZE.y as i32);
-    camera_pos / (chunk_size * tile_size)
+    tile_to_chunk(world_to_tile(*camera_pos))
 }
 
-pub fn spawn_chunks_around_camera(
+/// Whether the chunk at `chunk_pos` overlaps the area of half size
+/// `half_extents` around `center`.
+fn chunk_overlaps(chunk_pos: IVec2, center: Vec2, half_extents: Vec2) -> bool {
+    let tile_size = Vec2::new(TILE_SIZE.x, TILE_SIZE.y);
+    // Tiles are centered on their grid position, so the chunk starts half a
+    // tile before its origin.
+    let min = chunk_origin(chunk_pos) - tile_size / 2.;
+    let max = min + CHUNK_SIZE.as_vec2() * tile_size;
+    min.cmplt(center + half_extents).all() && max.cmpgt(center - half_extents).all()
+}
+
+/// How far past what the camera shows chunks are loaded and unloaded.
+#[derive(Resource, Clone, Debug)]
+pub struct WorldStreamingSettings {
+    /// Pixels past the edges of the view within which chunks are loaded.
+    pub load_margin: f32,
+    /// Pixels past the load margin a chunk has to be before it is unloaded,
+    /// so chunks on the edge don't flicker in and out.
+    pub hysteresis: f32,
+}
+
+impl Default for WorldStreamingSettings {
+    fn default() -> Self {
+        Self {
+            load_margin: 256.,
+            hysteresis: 64.,
+        }
+    }
+}
+
+impl WorldStreamingSettings {
+    /// Half size of the area chunks are loaded in, for a view of half size
+    /// `view`.
+    pub fn load_extents(&self, view: Vec2) -> Vec2 {
+        view + self.load_margin
+    }
+
+    /// Half size of the area chunks are kept in once loaded.
+    pub fn unload_extents(&self, view: Vec2) -> Vec2 {
+        self.load_extents(view) + self.hysteresis
+    }
+
+    /// The chunks to load around a camera at `camera_pos` showing `view`.
+    pub fn chunks_to_load(&self, camera_pos: Vec2, view: Vec2) -> impl Iterator<Item = IVec2> {
+        let extents = self.load_extents(view);
+        let min = camera_pos_to_chunk_pos(&(camera_pos - extents));
+        let max = camera_pos_to_chunk_pos(&(camera_pos + extents));
+        (min.y..=max.y)
+            .flat_map(move |y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+            .filter(move |&chunk_pos| chunk_overlaps(chunk_pos, camera_pos, extents))
+    }
+
+    /// Whether the chunk at `chunk_pos` is far enough from a camera at
+    /// `camera_pos` showing `view` to be unloaded.
+    pub fn should_unload(&self, camera_pos: Vec2, view: Vec2, chunk_pos: IVec2) -> bool {
+        !chunk_overlaps(chunk_pos, camera_pos, self.unload_extents(view))
+    }
+}
+
+/// Half size, in pixels, of the area `projection` shows in the primary
+/// window.
+pub fn view_half_extents(windows: &Windows, projection: &OrthographicProjection) -> Vec2 {
+    windows.get_primary().map_or(Vec2::ZERO, |window| {
+        Vec2::new(window.width(), window.height()) / 2. * projection.scale
+    })
+}
+
+/// Starts generating the missing chunks around the camera, nearest first, and
+/// gives up on the pending ones it moved away from.
+pub fn queue_chunks_around_camera(
+    windows: Res<Windows>,
+    camera_query: Query<(&GlobalTransform, &OrthographicProjection), With<Camera>>,
+    settings: Res<WorldStreamingSettings>,
+    chunk_manager: Res<ChunkManager>,
+    mut generator: ResMut<ChunkGenerator>,
+    terrain: Res<Terrain>,
+    seed: Res<WorldSeed>,
+) {
+    let pool = AsyncComputeTaskPool::get();
+    for (transform, projection) in camera_query.iter() {
+        let camera_pos = transform.translation().xy();
+        let camera_chunk_pos = camera_pos_to_chunk_pos(&camera_pos);
+        let view = view_half_extents(&windows, projection);
+        generator
+            .pending
+            .retain(|&chunk_pos, _| !settings.should_unload(camera_pos, view, chunk_pos));
+        let mut missing: Vec<IVec2> = settings
+            .chunks_to_load(camera_pos, view)
+            .filter(|chunk_pos| {
+                !chunk_manager.spawned_chunks.contains_key(chunk_pos)
+                    && !generator.pending.contains_key(chunk_pos)
+            })
+            .collect();
+        missing.sort_by_key(|&chunk_pos| by_distance(chunk_pos, camera_chunk_pos));
+        let room = generator
+            .max_pending
+            .saturating_sub(generator.pending.len());
+        for chunk_pos in missing.into_iter().take(room) {
+            let terrain = terrain.clone();
+            let seed = *seed;
+            let task = pool.spawn(async move { GeneratedChunk::new(&terrain, &seed, chunk_pos) });
+            generator.pending.insert(chunk_pos, task);
+        }
+    }
+}
+
+/// Spawns the chunks done generating, nearest to the camera first, up to the
+/// generator's budget.
+pub fn spawn_generated_chunks(
     mut commands: Commands,
     image_assets: Res<ImageAssets>,
     camera_query: Query<&GlobalTransform, With<Camera>>,
     mut chunk_manager: ResMut<ChunkManager>,
-    noise: Res<Noise>,
+    mut generator: ResMut<ChunkGenerator>,
 ) {
-    for transform in camera_query.iter() {
-        let camera_chunk_pos = camera_pos_to_chunk_pos(&transform.translation().xy());
-        for y in (camera_chunk_pos.y - 4)..(camera_chunk_pos.y + 4) {
-            for x in (camera_chunk_pos.x - 4)..(camera_chunk_pos.x + 4) {
-                if !chunk_manager.spawned_chunks.contains(&IVec2::new(x, y)) {
-                    chunk_manager.spawned_chunks.insert(IVec2::new(x, y));
-                    spawn_chunk(&mut commands, &image_assets, IVec2::new(x, y), &noise);
-                }
-            }
+    let Ok(camera_transform) = camera_query.get_single() else {
+        return;
+    };
+    let camera_chunk_pos = camera_pos_to_chunk_pos(&camera_transform.translation().xy());
+    let mut ready: Vec<IVec2> = generator
+        .pending
+        .iter()
+        .filter(|(_, task)| generator.wait || task.is_finished())
+        .map(|(&chunk_pos, _)| chunk_pos)
+        .collect();
+    ready.sort_by_key(|&chunk_pos| by_distance(chunk_pos, camera_chunk_pos));
+    ready.truncate(generator.spawn_budget);
+    for chunk_pos in ready {
+        let task = generator.pending.remove(&chunk_pos).unwrap();
+        let chunk = spawn_chunk(
+            &mut commands,
+            &image_assets,
+            chunk_pos,
+            future::block_on(task),
+            chunk_manager.deltas.get(&chunk_pos),
+        );
+        chunk_manager.spawned_chunks.insert(chunk_pos, chunk);
+        // Live again, they're written back when the chunk is unloaded.
+        if let Some(delta) = chunk_manager.deltas.get_mut(&chunk_pos) {
+            delta.pickups.clear();
         }
     }
 }
 
+/// Sort key putting the chunks nearest to `center` first, and ties in the
+/// same order on every run.
+fn by_distance(chunk_pos: IVec2, center: IVec2) -> (i32, i32, i32) {
+    let offset = chunk_pos - center;
+    (offset.dot(offset), chunk_pos.y, chunk_pos.x)
+}
+
+/// Despawns the chunks past the unload area of the
+/// [`WorldStreamingSettings`], keeping the pickups left on them.
 pub fn despawn_outofrange_chunks(
     mut commands: Commands,
-    camera_query: Query<&GlobalTransform, With<Camera>>,
-    chunks_query: Query<(Entity, &Transform), With<TilemapId>>,
+    windows: Res<Windows>,
+    camera_query: Query<(&GlobalTransform, &OrthographicProjection), With<Camera>>,
+    settings: Res<WorldStreamingSettings>,
     mut chunk_manager: ResMut<ChunkManager>,
+    pickups: Query<(&Pickup, &Transform)>,
 ) {
-    for camera_transform in camera_query.iter() {
-        for (entity, chunk_transform) in chunks_query.iter() {
-            let chunk_pos = chunk_transform.translation.xy();
-            let distance = camera_transform.translation().xy().distance(chunk_pos);
-            if distance > 320.0 {
-                let x = (chunk_pos.x / (CHUNK_SIZE.x as f32 * TILE_SIZE.x)).floor() as i32;
-                let y = (chunk_pos.y / (CHUNK_SIZE.y as f32 * TILE_SIZE.y)).floor() as i32;
-                chunk_manager.spawned_chunks.remove(&IVec2::new(x, y));
-                commands.entity(entity).despawn_recursive();
-            }
+    for (transform, projection) in camera_query.iter() {
+        let camera_pos = transform.translation().xy();
+        let view = view_half_extents(&windows, projection);
+        let out_of_range: Vec<IVec2> = chunk_manager
+            .spawned_chunks
+            .keys()
+            .copied()
+            .filter(|&chunk_pos| settings.should_unload(camera_pos, view, chunk_pos))
+            .collect();
+        store_pickups(&mut chunk_manager, &pickups, &out_of_range);
+        for chunk_pos in out_of_range {
+            let chunk = chunk_manager.spawned_chunks.remove(&chunk_pos).unwrap();
+            commands.entity(chunk.tilemap).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pixels across a chunk.
+    const CHUNK_PIXELS: f32 = CHUNK_SIZE.x as f32 * TILE_SIZE.x;
+
+    #[test]
+    fn camera_pos_to_chunk_pos_matches_chunk_bounds() {
+        assert_eq!(camera_pos_to_chunk_pos(&Vec2::ZERO), IVec2::ZERO);
+        // Chunk 0 spans from half a tile before the origin.
+        assert_eq!(camera_pos_to_chunk_pos(&Vec2::splat(-8.)), IVec2::ZERO);
+        assert_eq!(
+            camera_pos_to_chunk_pos(&Vec2::splat(CHUNK_PIXELS - 8.1)),
+            IVec2::ZERO
+        );
+        assert_eq!(
+            camera_pos_to_chunk_pos(&Vec2::splat(CHUNK_PIXELS - 8.)),
+            IVec2::ONE
+        );
+    }
+
+    #[test]
+    fn camera_pos_to_chunk_pos_rounds_negatives_down() {
+        assert_eq!(camera_pos_to_chunk_pos(&Vec2::splat(-8.1)), IVec2::NEG_ONE);
+        assert_eq!(
+            camera_pos_to_chunk_pos(&Vec2::new(-20., 20.)),
+            IVec2::new(-1, 0)
+        );
+        assert_eq!(
+            camera_pos_to_chunk_pos(&Vec2::splat(-CHUNK_PIXELS - 8.)),
+            IVec2::NEG_ONE
+        );
+        assert_eq!(
+            camera_pos_to_chunk_pos(&Vec2::splat(-CHUNK_PIXELS - 8.1)),
+            IVec2::splat(-2)
+        );
+        assert_eq!(
+            camera_pos_to_chunk_pos(&Vec2::new(-10. * CHUNK_PIXELS, 0.)),
+            IVec2::new(-10, 0)
+        );
+    }
+
+    #[test]
+    fn loaded_chunks_cover_the_view() {
+        let settings = WorldStreamingSettings {
+            load_margin: 0.,
+            hysteresis: 0.,
+        };
+        let view = Vec2::new(160., 90.);
+        let camera_pos = Vec2::new(-300., 45.);
+        let chunks: Vec<IVec2> = settings.chunks_to_load(camera_pos, view).collect();
+        for corner in [
+            camera_pos - view,
+            camera_pos + view * 0.999,
+            camera_pos + Vec2::new(view.x, -view.y) * 0.999,
+        ] {
+            assert!(chunks.contains(&camera_pos_to_chunk_pos(&corner)));
         }
+        for chunk_pos in chunks {
+            assert!(!settings.should_unload(camera_pos, view, chunk_pos));
+        }
+    }
+
+    #[test]
+    fn chunks_unload_only_past_the_hysteresis() {
+        let settings = WorldStreamingSettings {
+            load_margin: 0.,
+            hysteresis: 64.,
+        };
+        let view = Vec2::splat(100.);
+        // Chunk 1 starts 8 pixels short of the right edge of the view.
+        let chunk_pos = IVec2::new(1, 0);
+        let start = CHUNK_PIXELS - 8.;
+        let at_edge = Vec2::new(start - view.x + 8., 0.);
+        assert!(settings
+            .chunks_to_load(at_edge, view)
+            .any(|c| c == chunk_pos));
+
+        // Backing off a little takes it out of the load area but keeps it.
+        let backed_off = at_edge - Vec2::new(32., 0.);
+        assert!(!settings
+            .chunks_to_load(backed_off, view)
+            .any(|c| c == chunk_pos));
+        assert!(!settings.should_unload(backed_off, view, chunk_pos));
+
+        // Past the hysteresis it goes.
+        let gone = at_edge - Vec2::new(80., 0.);
+        assert!(settings.should_unload(gone, view, chunk_pos));
     }
 }
@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::tiles::TileStorage;
+use bevy_rapier2d::prelude::*;
+
+use crate::assets::FontAssets;
+use crate::components::{Gaucho, Projectile, Zombie};
+use crate::flow_field::FlowField;
+use crate::progression::ExperienceGem;
+use crate::resources::{ChunkManager, GameStats};
+use crate::scoring::{Combo, HighScores};
+use crate::status::Hazard;
+use crate::systems::chunk::ChunkGenerator;
+use crate::systems::hud::Hud;
+use crate::systems::menus::{spawn_button, spawn_screen, text, MenuButton};
+use crate::waves::WaveDirector;
+use crate::GameState;
+
+#[derive(Component)]
+pub struct GameOverScreen;
+
+pub fn freeze_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = false;
+}
+
+/// Color of the run that just ended in the high-score table.
+const LATEST_SCORE_COLOR: Color = Color::rgb(1.0, 0.8, 0.3);
+
+pub fn spawn_game_over_screen(
+    mut commands: Commands,
+    fonts: Res<FontAssets>,
+    stats: Res<GameStats>,
+    high_scores: Res<HighScores>,
+) {
+    spawn_screen(&mut commands, GameOverScreen).with_children(|parent| {
+        parent.spawn(text(&fonts, "GAME OVER", 64.0));
+        parent.spawn(text(&fonts, format!("Score: {}", stats.score), 40.0));
+        parent.spawn(text(
+            &fonts,
+            format!(
+                "Survived {:.1}s   Waves cleared: {}   Kills: {}   Damage taken: {}",
+                stats.survival_time.as_secs_f32(),
+                stats.waves_cleared,
+                stats.kills,
+                stats.damage_taken
+            ),
+            24.0,
+        ));
+        parent.spawn(text(&fonts, "HIGH SCORES", 28.0));
+        for (rank, entry) in high_scores.entries.iter().enumerate() {
+            let mut row = text(
+                &fonts,
+                format!(
+                    "{}. {}   {} kills   {} waves   {:.0}s",
+                    rank + 1,
+                    entry.score,
+                    entry.kills,
+                    entry.waves_cleared,
+                    entry.survival_time.as_secs_f32()
+                ),
+                20.0,
+            );
+            row.style.margin = UiRect::all(Val::Px(2.0));
+            if high_scores.latest == Some(rank) {
+                row.text.sections[0].style.color = LATEST_SCORE_COLOR;
+            }
+            parent.spawn(row);
+        }
+        spawn_button(parent, &fonts, "Play again", MenuButton::PlayAgain);
+        spawn_button(parent, &fonts, "Main menu", MenuButton::MainMenu);
+        parent.spawn(text(&fonts, "Press Enter to play again", 20.0));
+    });
+}
+
+pub fn restart_on_input(keys: Res<Input<KeyCode>>, mut state: ResMut<State<GameState>>) {
+    if keys.any_just_pressed([KeyCode::Return, KeyCode::R]) {
+        let _ = state.set(GameState::Playing);
+    }
+}
+
+/// Clears the previous run, so that entering [`GameState::Playing`] builds a
+/// fresh world and the main menu has nothing behind it.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn reset_world(
+    mut commands: Commands,
+    entities: Query<
+        Entity,
+        Or<(
+            With<Gaucho>,
+            With<Zombie>,
+            With<Projectile>,
+            With<TileStorage>,
+            With<GameOverScreen>,
+            With<Hud>,
+            With<ExperienceGem>,
+            With<Hazard>,
+        )>,
+    >,
+    mut chunk_manager: ResMut<ChunkManager>,
+    mut chunk_generator: ResMut<ChunkGenerator>,
+    mut stats: ResMut<GameStats>,
+    mut combo: ResMut<Combo>,
+    mut director: ResMut<WaveDirector>,
+    mut flow_field: ResMut<FlowField>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    *chunk_manager = ChunkManager::default();
+    chunk_generator.clear();
+    *stats = GameStats::default();
+    *combo = Combo::default();
+    *director = WaveDirector::default();
+    *flow_field = FlowField::default();
+    rapier_config.physics_pipeline_active = true;
+}
@@ -1,130 +1,219 @@
-use bevy::{
-    math::{vec2, vec3},
-    prelude::*,
-};
-use std::{f32::consts::PI, ops::Deref};
+use bevy::{math::vec3, prelude::*};
+use rand::Rng;
 
 use crate::{
-    animations::{Animation, AnimationBundle, FaconAnimationResource},
-    components::{Damage, Gaucho, HitReaction},
+    animations::{
+        Action, Animation, AnimationBundle, AnimationDefinition, AnimationFinished, AnimationKey,
+    },
+    assets::{AnimationAssets, DataAssets},
+    components::{
+        AimDirection, Damage, Gaucho, Health, HitReaction, Inflicts, Invulnerability, Knockback,
+        PlayerIntent,
+    },
+    pickups::PowerUps,
+    progression::{UpgradeTable, Upgrades},
+    resources::{CombatRng, GameStats},
+    settings::GameSettings,
+    status::StatusEffects,
+    systems::projectiles::spawn_projectile,
+    weapons::{Arsenal, Delivery},
+    GameState,
 };
 
 use bevy_rapier2d::prelude::*;
 
+pub fn switch_weapon(mut gaucho: Query<(&PlayerIntent, &mut Arsenal), With<Gaucho>>) {
+    let (intent, mut arsenal) = gaucho.single_mut();
+    let previous = arsenal.equipped;
+    if let Some(slot) = intent.equip {
+        arsenal.equip(slot);
+    }
+    if intent.cycle_weapon != 0 {
+        arsenal.cycle(intent.cycle_weapon);
+    }
+    if arsenal.equipped != previous {
+        info!("Equipped {}", arsenal.equipped().definition().name);
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn attack(
     mut commands: Commands,
-    buttons: Res<Input<MouseButton>>,
-    windows: Res<Windows>,
-    gaucho: Query<Entity, With<Gaucho>>,
+    mut gaucho: Query<
+        (
+            Entity,
+            &Transform,
+            &PlayerIntent,
+            &AimDirection,
+            &mut Arsenal,
+            &Upgrades,
+            &PowerUps,
+            &StatusEffects,
+        ),
+        With<Gaucho>,
+    >,
+    time: Res<Time>,
     asset_server: Res<AssetServer>,
     audio: Res<Audio>,
-    facon_resource: Res<FaconAnimationResource>,
+    settings: Res<GameSettings>,
+    animation_assets: Res<AnimationAssets>,
+    animations: Res<Assets<AnimationDefinition>>,
+    data_assets: Res<DataAssets>,
+    upgrade_tables: Res<Assets<UpgradeTable>>,
+    mut random: ResMut<CombatRng>,
 ) {
-    if buttons.just_pressed(MouseButton::Left) {
-        let shoot = asset_server.load("sounds/knife_attack.ogg");
-        audio.play(shoot);
-
-        let window = windows.get_primary().unwrap();
-        if let Some(position) = window.cursor_position() {
-            let screen_center = vec2(window.width() / 2., window.height() / 2.);
-            let mouse_coordinates = (position - screen_center).normalize() * 10.0;
-            let is_looking_up = mouse_coordinates.y > 5.0;
-            let is_looking_down = mouse_coordinates.y < -5.0;
-            let is_looking_left = mouse_coordinates.x < 0.0;
+    let (gaucho, gaucho_transform, intent, aim, mut arsenal, upgrades, power_ups, statuses) =
+        gaucho.single_mut();
+    let table = upgrade_tables.get(&data_assets.upgrades).unwrap();
+    let stats = power_ups.boost(upgrades.stats(table));
+    arsenal.cooldown.tick(time.delta());
+    if intent.attack && arsenal.cooldown.finished() && !statuses.stunned() {
+        let weapon_kind = arsenal.equipped();
+        let weapon = weapon_kind.definition();
+        let shoot = asset_server.load(weapon.sound);
+        audio.play_with_settings(shoot, settings.sound());
 
-            let direction = match (is_looking_up, is_looking_down, is_looking_left) {
-                (true, _, _) => PI / 2.,
-                (_, true, _) => PI * 1.5,
-                (_, _, true) => PI,
-                (false, false, false) => 0.,
-            };
+        let rotation = Quat::from_rotation_z(aim.angle());
+        arsenal.cooldown = Timer::from_seconds(weapon.cooldown * stats.cooldown, TimerMode::Once);
+        let damage = random.gen_range(weapon.damage.0..=weapon.damage.1);
+        let damage = (damage as f32 * stats.damage).round() as usize;
+        let mut weapon_bundle = AnimationBundle::from(
+            animations
+                .get((weapon.animation)(&animation_assets))
+                .unwrap(),
+        );
+        match weapon.delivery {
+            Delivery::Melee => {
+                weapon_bundle.sprite.transform.translation = vec3(weapon.reach, 0., 0.);
+                weapon_bundle
+                    .sprite
+                    .transform
+                    .rotate_around(Vec3::ZERO, rotation);
+                let swing = commands
+                    .spawn(weapon_bundle)
+                    .insert(RigidBody::Fixed)
+                    .insert(Collider::from(weapon.hitbox))
+                    .insert(Sensor)
+                    .insert(Damage(damage))
+                    .insert(Knockback(weapon.knockback))
+                    .insert(Inflicts(weapon.inflicts))
+                    .insert(weapon_kind)
+                    .id();
+                commands.entity(gaucho).add_child(swing);
+            }
+            Delivery::Thrown(spec) | Delivery::Ranged(spec) => {
+                let heading = (rotation * Vec3::X).truncate();
+                weapon_bundle.sprite.transform =
+                    Transform::from_translation(gaucho_transform.translation)
+                        .with_rotation(rotation);
+                let projectile = spawn_projectile(
+                    &mut commands,
+                    weapon_bundle,
+                    spec.projectile(heading, weapon.reach, false),
+                    Collider::from(weapon.hitbox),
+                    damage,
+                    weapon.knockback,
+                );
+                commands
+                    .entity(projectile)
+                    .insert(Inflicts(weapon.inflicts))
+                    .insert(weapon_kind);
+            }
+        }
+    }
+}
 
-            let mut facon_bundle = Into::<AnimationBundle>::into(facon_resource.deref().to_owned());
-            facon_bundle.sprite.transform.translation = vec3(11., 0., 0.);
-            facon_bundle
-                .sprite
-                .transform
-                .rotate_around(Vec3::ZERO, Quat::from_rotation_z(direction));
-            let facon = commands
-                .spawn(facon_bundle)
-                .insert(RigidBody::Fixed)
-                .insert(Collider::cuboid(4.0, 5.))
-                .insert(Sensor)
-                .insert(Damage(40))
-                .id();
-            commands.entity(gaucho.single()).add_child(facon);
+/// A swing stops hurting as soon as its animation ends, even though the
+/// facón itself is only despawned on the next frame.
+pub fn sheathe_finished_weapons(
+    mut commands: Commands,
+    mut finished: EventReader<AnimationFinished>,
+    weapons: Query<(), With<Damage>>,
+) {
+    for event in finished.iter() {
+        if event.state.action == Action::Attack && weapons.contains(event.entity) {
+            commands
+                .entity(event.entity)
+                .remove::<Damage>()
+                .remove::<Collider>();
         }
     }
 }
 
+#[allow(clippy::type_complexity)]
 pub fn sprite_movement(
-    windows: Res<Windows>,
-    keyboard_input: Res<Input<KeyCode>>,
     mut sprite_position: Query<
         (
-            &mut Transform,
+            &mut KinematicCharacterController,
             &mut Animation,
+            &PlayerIntent,
+            &mut AimDirection,
             &mut HitReaction,
+            &mut Invulnerability,
             &mut TextureAtlasSprite,
+            &Upgrades,
+            &PowerUps,
+            &StatusEffects,
         ),
         With<Gaucho>,
     >,
     time: Res<Time>,
+    data_assets: Res<DataAssets>,
+    upgrade_tables: Res<Assets<UpgradeTable>>,
 ) {
-    let window = windows.get_primary().unwrap();
-    for (mut transform, mut animation, mut hit_reaction, mut sprite) in sprite_position.iter_mut() {
-        if let Some(position) = window.cursor_position() {
-            let screen_center = vec2(window.width() / 2., window.height() / 2.);
-            let mouse_coordinates = (position - screen_center).normalize() * 10.0;
-            let is_looking_up = mouse_coordinates.y > 5.0;
-            let is_looking_down = mouse_coordinates.y < -5.0;
-            let is_looking_left = mouse_coordinates.x < 0.0;
-
-            let direction = match (is_looking_up, is_looking_down, is_looking_left) {
-                (true, _, _) => "Up",
-                (_, true, _) => "Down",
-                (_, _, true) => "Left",
-                (false, false, false) => "Right",
-            };
-            let mut speed = Vec2::ZERO;
-
-            if keyboard_input.any_pressed([KeyCode::Up, KeyCode::W]) {
-                speed.y = 1.0;
-            }
-            if keyboard_input.any_pressed([KeyCode::Down, KeyCode::S]) {
-                speed.y = -1.0;
-            }
-            if keyboard_input.any_pressed([KeyCode::Left, KeyCode::A]) {
-                speed.x = -1.0;
-            }
-            if keyboard_input.any_pressed([KeyCode::Right, KeyCode::D]) {
-                speed.x = 1.0;
-            }
-
-            if speed == Vec2::ZERO {
-                let state = format!("{direction}Idle",);
-                animation.set_state(state);
-            } else {
-                let state = format!("{direction}Walking",);
-                animation.set_state(state);
+    let table = upgrade_tables.get(&data_assets.upgrades).unwrap();
+    for (
+        mut controller,
+        mut animation,
+        intent,
+        mut aim,
+        mut hit_reaction,
+        mut invulnerability,
+        mut sprite,
+        upgrades,
+        power_ups,
+        statuses,
+    ) in sprite_position.iter_mut()
+    {
+        if let Some(direction) = intent.aim {
+            aim.0 = direction;
+        }
+        let direction = aim.facing();
 
-                speed = speed.normalize() * 2.0;
-                transform.translation.x += speed.x;
-                transform.translation.y += speed.y;
-            }
+        // Moved by the character controller, which slides along solid tiles.
+        let mut translation = Vec2::ZERO;
+        if intent.movement == Vec2::ZERO || statuses.stunned() {
+            animation.set_state(AnimationKey::new(direction, Action::Idle));
+        } else {
+            animation.set_state(AnimationKey::new(direction, Action::Walking));
+            translation += intent.movement
+                * power_ups.boost(upgrades.stats(table)).move_speed
+                * statuses.speed_multiplier();
         }
         if hit_reaction.length() > 0.001 {
-            transform.translation.x += hit_reaction.x;
-            transform.translation.y += hit_reaction.y;
+            translation += hit_reaction.0;
             hit_reaction.0 *= 0.75;
-            sprite.color = if time.elapsed().as_millis() % 100 < 50 {
-                Color::RED
-            } else {
-                Color::WHITE
-            };
         } else {
             hit_reaction.0 = Vec2::ZERO;
-            sprite.color = Color::WHITE;
         }
+        controller.translation = (translation != Vec2::ZERO).then_some(translation);
+        invulnerability.tick(time.delta());
+        sprite.color = if !invulnerability.finished() && time.elapsed().as_millis() % 100 < 50 {
+            Color::RED
+        } else {
+            statuses.tint(Color::WHITE)
+        };
+    }
+}
+
+pub fn track_survival_time(time: Res<Time>, mut stats: ResMut<GameStats>) {
+    stats.survival_time += time.delta();
+}
+
+pub fn check_death(gaucho: Query<&Health, With<Gaucho>>, mut state: ResMut<State<GameState>>) {
+    if gaucho.single().current == 0 {
+        // Another transition may already be queued this frame, a level up or
+        // a pause; he's still dead once back, so it's only put off.
+        let _ = state.set(GameState::GameOver);
     }
 }
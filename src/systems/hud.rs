@@ -0,0 +1,234 @@
+use bevy::prelude::*;
+
+use crate::assets::FontAssets;
+use crate::components::{Gaucho, Health};
+use crate::progression::Experience;
+use crate::resources::GameStats;
+use crate::scoring::Combo;
+use crate::systems::menus::text;
+use crate::waves::{WaveCleared, WaveDirector, WavePhase, WaveStarted};
+use crate::weapons::Arsenal;
+
+/// Root of the HUD, torn down along with the run.
+#[derive(Component)]
+pub struct Hud;
+
+#[derive(Component)]
+pub struct HealthFill;
+
+#[derive(Component)]
+pub struct HealthLabel;
+
+#[derive(Component)]
+pub struct WaveLabel;
+
+/// Kills, score and survival time.
+#[derive(Component)]
+pub struct StatsLabel;
+
+#[derive(Component)]
+pub struct WeaponLabel;
+
+#[derive(Component)]
+pub struct LevelLabel;
+
+/// Fills up with the experience gathered towards the next level.
+#[derive(Component)]
+pub struct ExperienceFill;
+
+/// Fills up as the equipped weapon gets ready to attack again.
+#[derive(Component)]
+pub struct CooldownFill;
+
+const HEALTH_COLOR: Color = Color::rgb(0.75, 0.1, 0.1);
+const COOLDOWN_COLOR: Color = Color::rgb(0.9, 0.75, 0.3);
+const EXPERIENCE_COLOR: Color = Color::rgb(0.3, 0.9, 1.0);
+const BAR_BACKGROUND: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+
+/// A bar `width` pixels wide, with its fill marked by `fill`.
+fn spawn_bar(parent: &mut ChildBuilder, width: f32, color: Color, fill: impl Component) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(width), Val::Px(16.0)),
+                margin: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            background_color: BAR_BACKGROUND.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                        ..default()
+                    },
+                    background_color: color.into(),
+                    ..default()
+                })
+                .insert(fill);
+        });
+}
+
+/// Lays out the HUD with empty labels; the systems below fill them in as
+/// soon as it's there.
+pub fn spawn_hud(mut commands: Commands, fonts: Res<FontAssets>) {
+    let column = |justify_content| NodeBundle {
+        style: Style {
+            size: Size::new(Val::Auto, Val::Percent(100.0)),
+            flex_direction: FlexDirection::Column,
+            justify_content,
+            ..default()
+        },
+        ..default()
+    };
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                justify_content: JustifyContent::SpaceBetween,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(Hud)
+        .with_children(|parent| {
+            parent
+                .spawn(column(JustifyContent::SpaceBetween))
+                .with_children(|parent| {
+                    parent.spawn(NodeBundle::default()).with_children(|parent| {
+                        spawn_bar(parent, 200.0, HEALTH_COLOR, HealthFill);
+                        parent.spawn(text(&fonts, "", 24.0)).insert(HealthLabel);
+                    });
+                    parent.spawn(NodeBundle::default()).with_children(|parent| {
+                        parent.spawn(text(&fonts, "", 24.0)).insert(WeaponLabel);
+                        spawn_bar(parent, 120.0, COOLDOWN_COLOR, CooldownFill);
+                    });
+                });
+            parent
+                .spawn(column(JustifyContent::FlexStart))
+                .with_children(|parent| {
+                    parent.spawn(text(&fonts, "", 32.0)).insert(WaveLabel);
+                    parent.spawn(NodeBundle::default()).with_children(|parent| {
+                        parent.spawn(text(&fonts, "", 24.0)).insert(LevelLabel);
+                        spawn_bar(parent, 160.0, EXPERIENCE_COLOR, ExperienceFill);
+                    });
+                });
+            parent
+                .spawn(column(JustifyContent::FlexStart))
+                .with_children(|parent| {
+                    parent.spawn(text(&fonts, "", 24.0)).insert(StatsLabel);
+                });
+        });
+}
+
+pub fn update_health(
+    gaucho: Query<(&Health, ChangeTrackers<Health>), With<Gaucho>>,
+    added: Query<(), Added<HealthLabel>>,
+    mut fills: Query<&mut Style, With<HealthFill>>,
+    mut labels: Query<&mut Text, With<HealthLabel>>,
+) {
+    let Ok((health, tracker)) = gaucho.get_single() else {
+        return;
+    };
+    if !tracker.is_changed() && added.is_empty() {
+        return;
+    }
+    for mut style in fills.iter_mut() {
+        style.size.width = Val::Percent(100.0 * health.current as f32 / health.max as f32);
+    }
+    for mut text in labels.iter_mut() {
+        text.sections[0].value = format!("{}/{}", health.current, health.max);
+    }
+}
+
+pub fn update_weapon(
+    gaucho: Query<(&Arsenal, ChangeTrackers<Arsenal>), With<Gaucho>>,
+    added: Query<(), Added<WeaponLabel>>,
+    mut fills: Query<&mut Style, With<CooldownFill>>,
+    mut labels: Query<&mut Text, With<WeaponLabel>>,
+) {
+    let Ok((arsenal, tracker)) = gaucho.get_single() else {
+        return;
+    };
+    if !tracker.is_changed() && added.is_empty() {
+        return;
+    }
+    for mut style in fills.iter_mut() {
+        style.size.width = Val::Percent(100.0 * arsenal.cooldown.percent());
+    }
+    for mut text in labels.iter_mut() {
+        text.sections[0].value = arsenal.equipped().definition().name.to_string();
+    }
+}
+
+pub fn update_experience(
+    gaucho: Query<(&Experience, ChangeTrackers<Experience>), With<Gaucho>>,
+    added: Query<(), Added<LevelLabel>>,
+    mut fills: Query<&mut Style, With<ExperienceFill>>,
+    mut labels: Query<&mut Text, With<LevelLabel>>,
+) {
+    let Ok((experience, tracker)) = gaucho.get_single() else {
+        return;
+    };
+    if !tracker.is_changed() && added.is_empty() {
+        return;
+    }
+    for mut style in fills.iter_mut() {
+        style.size.width = Val::Percent(100.0 * experience.progress());
+    }
+    for mut text in labels.iter_mut() {
+        text.sections[0].value = format!("Level {}", experience.level);
+    }
+}
+
+pub fn update_wave(
+    mut started: EventReader<WaveStarted>,
+    mut cleared: EventReader<WaveCleared>,
+    director: Res<WaveDirector>,
+    added: Query<(), Added<WaveLabel>>,
+    mut labels: Query<&mut Text, With<WaveLabel>>,
+) {
+    // The events only say when to look; the director says where the waves are.
+    let happened = started.iter().count() + cleared.iter().count() > 0;
+    if !happened && added.is_empty() {
+        return;
+    }
+    let value = match director.wave {
+        0 => "Get ready".to_string(),
+        wave if matches!(director.phase, WavePhase::Resting(_)) => {
+            format!("Wave {wave} cleared")
+        }
+        wave => format!("Wave {wave}"),
+    };
+    for mut text in labels.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
+
+pub fn update_stats(
+    stats: Res<GameStats>,
+    combo: Res<Combo>,
+    added: Query<(), Added<StatsLabel>>,
+    mut labels: Query<&mut Text, With<StatsLabel>>,
+) {
+    if !stats.is_changed() && !combo.is_changed() && added.is_empty() {
+        return;
+    }
+    let seconds = stats.survival_time.as_secs();
+    let multiplier = match combo.multiplier() {
+        1 => String::new(),
+        multiplier => format!(" x{multiplier}"),
+    };
+    for mut text in labels.iter_mut() {
+        text.sections[0].value = format!(
+            "Kills {}   Score {}{multiplier}   Pesos {}   {:02}:{:02}",
+            stats.kills,
+            stats.score,
+            stats.coins,
+            seconds / 60,
+            seconds % 60
+        );
+    }
+}
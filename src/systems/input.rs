@@ -0,0 +1,143 @@
+use bevy::input::mouse::MouseWheel;
+use bevy::math::vec2;
+use bevy::prelude::*;
+
+use crate::components::{Gaucho, PlayerIntent};
+use crate::input::{Binding, InputBindings, PlayerAction};
+
+/// Remembers whether the player last aimed with the right stick or with the
+/// mouse, so a cursor resting in the window doesn't fight the stick.
+#[derive(Default)]
+pub struct AimSource {
+    stick: bool,
+    last_cursor: Option<Vec2>,
+}
+
+/// Turns the keyboard, mouse and first connected gamepad into the gaucho's
+/// [`PlayerIntent`] for this frame.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn read_player_intent(
+    bindings: Res<InputBindings>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    gamepads: Res<Gamepads>,
+    mut wheel: EventReader<MouseWheel>,
+    windows: Res<Windows>,
+    camera: Query<(&Transform, &OrthographicProjection), With<Camera>>,
+    mut gaucho: Query<(&Transform, &mut PlayerIntent), (With<Gaucho>, Without<Camera>)>,
+    mut aim_source: Local<AimSource>,
+) {
+    let gamepad = gamepads.iter().next();
+    let check = |action: PlayerAction, just: bool| {
+        bindings
+            .bindings(action)
+            .iter()
+            .any(|&binding| match binding {
+                Binding::Key(key) if just => keys.just_pressed(key),
+                Binding::Key(key) => keys.pressed(key),
+                Binding::Mouse(button) if just => buttons.just_pressed(button),
+                Binding::Mouse(button) => buttons.pressed(button),
+                Binding::Gamepad(button_type) => {
+                    let Some(gamepad) = gamepad else {
+                        return false;
+                    };
+                    let button = GamepadButton::new(gamepad, button_type);
+                    if just {
+                        gamepad_buttons.just_pressed(button)
+                    } else {
+                        gamepad_buttons.pressed(button)
+                    }
+                }
+            })
+    };
+    let pressed = |action| check(action, false);
+    let just_pressed = |action| check(action, true);
+    let stick = |x, y| {
+        let Some(gamepad) = gamepad else {
+            return Vec2::ZERO;
+        };
+        let axis = |axis_type| axes.get(GamepadAxis::new(gamepad, axis_type)).unwrap_or(0.);
+        let stick = vec2(axis(x), axis(y));
+        if stick.length() < bindings.stick_deadzone {
+            Vec2::ZERO
+        } else {
+            stick.clamp_length_max(1.)
+        }
+    };
+
+    let mut movement = Vec2::ZERO;
+    if pressed(PlayerAction::MoveUp) {
+        movement.y += 1.;
+    }
+    if pressed(PlayerAction::MoveDown) {
+        movement.y -= 1.;
+    }
+    if pressed(PlayerAction::MoveLeft) {
+        movement.x -= 1.;
+    }
+    if pressed(PlayerAction::MoveRight) {
+        movement.x += 1.;
+    }
+    movement = movement.normalize_or_zero();
+    if movement == Vec2::ZERO {
+        movement = stick(GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY);
+    }
+
+    let window = windows.get_primary().unwrap();
+    let cursor = window.cursor_position();
+    if cursor.is_some() && cursor != aim_source.last_cursor
+        || buttons.get_just_pressed().next().is_some()
+    {
+        aim_source.stick = false;
+    }
+    aim_source.last_cursor = cursor;
+    let right_stick = stick(GamepadAxisType::RightStickX, GamepadAxisType::RightStickY);
+    if right_stick != Vec2::ZERO {
+        aim_source.stick = true;
+    }
+
+    let mut cycle_weapon = 0;
+    if just_pressed(PlayerAction::NextWeapon) {
+        cycle_weapon += 1;
+    }
+    if just_pressed(PlayerAction::PreviousWeapon) {
+        cycle_weapon -= 1;
+    }
+    for event in wheel.iter() {
+        if event.y != 0. {
+            cycle_weapon -= event.y.signum() as isize;
+        }
+    }
+    let equip = [
+        PlayerAction::Weapon1,
+        PlayerAction::Weapon2,
+        PlayerAction::Weapon3,
+    ]
+    .into_iter()
+    .position(just_pressed);
+
+    let (camera_transform, projection) = camera.single();
+    for (transform, mut intent) in gaucho.iter_mut() {
+        let aim = if aim_source.stick {
+            Some(right_stick).filter(|aim| *aim != Vec2::ZERO)
+        } else {
+            cursor.map(|cursor| {
+                let screen_center = vec2(window.width() / 2., window.height() / 2.);
+                let cursor = camera_transform.translation.truncate()
+                    + (cursor - screen_center) * projection.scale;
+                cursor - transform.translation.truncate()
+            })
+        };
+        *intent = PlayerIntent {
+            movement,
+            aim: aim
+                .map(Vec2::normalize_or_zero)
+                .filter(|aim| *aim != Vec2::ZERO),
+            attack: just_pressed(PlayerAction::Attack),
+            equip,
+            cycle_weapon,
+        };
+    }
+}
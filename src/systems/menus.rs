@@ -0,0 +1,323 @@
+use bevy::app::AppExit;
+use bevy::audio::AudioSink;
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy::window::WindowMode;
+use bevy_rapier2d::prelude::*;
+
+use crate::assets::FontAssets;
+use crate::progression::{Upgrade, UpgradeChosen};
+use crate::save::{PendingLoad, SaveGame};
+use crate::settings::GameSettings;
+use crate::systems::save::SaveRequested;
+use crate::{Ambience, GameState, AMBIENCE_VOLUME};
+
+#[derive(Component)]
+pub struct MainMenuScreen;
+
+#[derive(Component)]
+pub struct PauseScreen;
+
+#[derive(Component)]
+pub struct SettingsScreen;
+
+/// Where the main menu says why the save couldn't be loaded.
+#[derive(Component)]
+pub struct MenuNotice;
+
+/// Text in the settings screen showing the current value of a setting.
+#[derive(Component, Clone, Copy)]
+pub enum SettingLabel {
+    Volume,
+    Fullscreen,
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MenuButton {
+    NewGame,
+    Continue,
+    Settings,
+    Quit,
+    Resume,
+    Save,
+    MainMenu,
+    PlayAgain,
+    VolumeDown,
+    VolumeUp,
+    Fullscreen,
+    Back,
+    Upgrade(Upgrade),
+}
+
+const BUTTON_COLOR: Color = Color::rgb(0.25, 0.2, 0.15);
+const HOVERED_BUTTON_COLOR: Color = Color::rgb(0.4, 0.3, 0.2);
+const PRESSED_BUTTON_COLOR: Color = Color::rgb(0.55, 0.4, 0.25);
+
+/// A full screen column of centered UI, dimming the game behind it.
+pub fn spawn_screen<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    marker: impl Component,
+) -> EntityCommands<'w, 's, 'a> {
+    let mut screen = commands.spawn(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+        ..default()
+    });
+    screen.insert(marker);
+    screen
+}
+
+pub fn text(fonts: &FontAssets, value: impl Into<String>, font_size: f32) -> TextBundle {
+    TextBundle::from_section(
+        value,
+        TextStyle {
+            font: fonts.main.clone(),
+            font_size,
+            color: Color::WHITE,
+        },
+    )
+    .with_style(Style {
+        margin: UiRect::all(Val::Px(8.0)),
+        ..default()
+    })
+}
+
+pub fn spawn_button(
+    parent: &mut ChildBuilder,
+    fonts: &FontAssets,
+    label: &str,
+    button: MenuButton,
+) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(240.0), Val::Px(48.0)),
+                margin: UiRect::all(Val::Px(6.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: BUTTON_COLOR.into(),
+            ..default()
+        })
+        .insert(button)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font: fonts.main.clone(),
+                    font_size: 28.0,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+}
+
+pub fn spawn_main_menu(mut commands: Commands, fonts: Res<FontAssets>) {
+    spawn_screen(&mut commands, MainMenuScreen).with_children(|parent| {
+        parent.spawn(text(&fonts, "GAUCHOS VS ZOMBIES", 64.0));
+        spawn_button(parent, &fonts, "New game", MenuButton::NewGame);
+        if std::path::Path::new(SaveGame::PATH).exists() {
+            spawn_button(parent, &fonts, "Continue", MenuButton::Continue);
+        }
+        spawn_button(parent, &fonts, "Settings", MenuButton::Settings);
+        spawn_button(parent, &fonts, "Quit", MenuButton::Quit);
+        parent.spawn(text(&fonts, "", 20.0)).insert(MenuNotice);
+    });
+}
+
+pub fn spawn_pause_menu(mut commands: Commands, fonts: Res<FontAssets>) {
+    spawn_screen(&mut commands, PauseScreen).with_children(|parent| {
+        parent.spawn(text(&fonts, "PAUSED", 64.0));
+        spawn_button(parent, &fonts, "Resume", MenuButton::Resume);
+        spawn_button(parent, &fonts, "Save", MenuButton::Save);
+        spawn_button(parent, &fonts, "Settings", MenuButton::Settings);
+        spawn_button(parent, &fonts, "Main menu", MenuButton::MainMenu);
+    });
+}
+
+pub fn spawn_settings_screen(mut commands: Commands, fonts: Res<FontAssets>) {
+    spawn_screen(&mut commands, SettingsScreen).with_children(|parent| {
+        parent.spawn(text(&fonts, "SETTINGS", 64.0));
+        parent
+            .spawn(text(&fonts, "", 32.0))
+            .insert(SettingLabel::Volume);
+        parent.spawn(NodeBundle::default()).with_children(|parent| {
+            spawn_button(parent, &fonts, "Quieter", MenuButton::VolumeDown);
+            spawn_button(parent, &fonts, "Louder", MenuButton::VolumeUp);
+        });
+        parent
+            .spawn(text(&fonts, "", 32.0))
+            .insert(SettingLabel::Fullscreen);
+        spawn_button(parent, &fonts, "Toggle", MenuButton::Fullscreen);
+        parent.spawn(text(
+            &fonts,
+            format!("Edit {} to rebind the controls", crate::InputBindings::PATH),
+            20.0,
+        ));
+        spawn_button(parent, &fonts, "Back", MenuButton::Back);
+    });
+}
+
+/// Keeps the settings screen showing the current settings.
+pub fn update_setting_labels(
+    settings: Res<GameSettings>,
+    mut labels: Query<(&SettingLabel, &mut Text)>,
+    added: Query<(), Added<SettingLabel>>,
+) {
+    if !settings.is_changed() && added.is_empty() {
+        return;
+    }
+    for (label, mut text) in labels.iter_mut() {
+        text.sections[0].value = match label {
+            SettingLabel::Volume => format!("Volume: {:.0}%", settings.volume * 100.),
+            SettingLabel::Fullscreen => format!(
+                "Fullscreen: {}",
+                if settings.fullscreen { "on" } else { "off" }
+            ),
+        };
+    }
+}
+
+pub fn despawn_screen<T: Component>(mut commands: Commands, screens: Query<Entity, With<T>>) {
+    for screen in screens.iter() {
+        commands.entity(screen).despawn_recursive();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn highlight_buttons(
+    mut buttons: Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<Button>)>,
+) {
+    for (interaction, mut color) in buttons.iter_mut() {
+        *color = match interaction {
+            Interaction::Clicked => PRESSED_BUTTON_COLOR,
+            Interaction::Hovered => HOVERED_BUTTON_COLOR,
+            Interaction::None => BUTTON_COLOR,
+        }
+        .into();
+    }
+}
+
+// State changes below are ignored when another one is already queued this
+// frame, like a click landing on the same frame as Escape.
+
+#[allow(clippy::too_many_arguments)]
+pub fn press_menu_buttons(
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut state: ResMut<State<GameState>>,
+    mut settings: ResMut<GameSettings>,
+    mut pending_load: ResMut<PendingLoad>,
+    mut save_requests: EventWriter<SaveRequested>,
+    mut upgrades_chosen: EventWriter<UpgradeChosen>,
+    mut exit: EventWriter<AppExit>,
+    mut notices: Query<&mut Text, With<MenuNotice>>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button {
+            MenuButton::NewGame => {
+                pending_load.0 = None;
+                let _ = state.set(GameState::Playing);
+            }
+            MenuButton::Continue => match SaveGame::read(SaveGame::PATH) {
+                Ok(save) => {
+                    pending_load.0 = Some(save);
+                    let _ = state.set(GameState::Playing);
+                }
+                Err(error) => {
+                    let notice = format!("Can't load {}: {error}", SaveGame::PATH);
+                    warn!("{notice}");
+                    for mut text in notices.iter_mut() {
+                        text.sections[0].value = notice.clone();
+                    }
+                }
+            },
+            MenuButton::Settings => {
+                let _ = state.push(GameState::Settings);
+            }
+            MenuButton::Quit => exit.send(AppExit),
+            MenuButton::Resume | MenuButton::Back => {
+                let _ = state.pop();
+            }
+            MenuButton::Save => save_requests.send(SaveRequested),
+            MenuButton::MainMenu => {
+                let _ = state.replace(GameState::MainMenu);
+            }
+            MenuButton::PlayAgain => {
+                let _ = state.set(GameState::Playing);
+            }
+            MenuButton::VolumeDown | MenuButton::VolumeUp => {
+                let steps = if *button == MenuButton::VolumeUp {
+                    1.
+                } else {
+                    -1.
+                };
+                settings.change_volume(steps);
+                settings.write(GameSettings::PATH);
+            }
+            MenuButton::Fullscreen => {
+                settings.fullscreen = !settings.fullscreen;
+                settings.write(GameSettings::PATH);
+            }
+            MenuButton::Upgrade(upgrade) => upgrades_chosen.send(UpgradeChosen(*upgrade)),
+        }
+    }
+}
+
+/// Escape pauses the game, and backs out of the pause and settings screens.
+pub fn back_on_escape(keys: Res<Input<KeyCode>>, mut state: ResMut<State<GameState>>) {
+    if !keys.just_pressed(KeyCode::Escape) {
+        return;
+    }
+    match state.current() {
+        GameState::Playing => {
+            let _ = state.push(GameState::Paused);
+        }
+        GameState::Paused | GameState::Settings => {
+            let _ = state.pop();
+        }
+        _ => {}
+    }
+}
+
+/// Stops the clock and the physics, so nothing driven by either moves while
+/// the game is paused.
+pub fn pause_game(mut time: ResMut<Time>, mut rapier_config: ResMut<RapierConfiguration>) {
+    time.pause();
+    rapier_config.physics_pipeline_active = false;
+}
+
+pub fn resume_game(mut time: ResMut<Time>, mut rapier_config: ResMut<RapierConfiguration>) {
+    time.unpause();
+    rapier_config.physics_pipeline_active = true;
+}
+
+pub fn apply_settings(
+    settings: Res<GameSettings>,
+    mut windows: ResMut<Windows>,
+    ambience: Option<Res<Ambience>>,
+    sinks: Res<Assets<AudioSink>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    if let Some(window) = windows.get_primary_mut() {
+        window.set_mode(if settings.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        });
+    }
+    if let Some(sink) = ambience.and_then(|ambience| sinks.get(&ambience.0)) {
+        sink.set_volume(AMBIENCE_VOLUME * settings.volume);
+    }
+}
@@ -1,4 +1,17 @@
 pub mod camera;
 pub mod chunk;
+pub mod game_over;
 pub mod gaucho;
+pub mod hud;
+pub mod input;
+pub mod menus;
+pub mod pickups;
+pub mod progression;
+pub mod projectiles;
+pub mod props;
+pub mod save;
+pub mod scoring;
+pub mod status;
+pub mod steering;
+pub mod waves;
 pub mod zombies;
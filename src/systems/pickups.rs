@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+use bevy::utils::Duration;
+use bevy_rapier2d::prelude::*;
+
+use crate::components::{Gaucho, Health};
+use crate::pickups::{Pickup, PickupEffect, PickupKind, PowerUps, SavedPickup, PICKUP_LIFETIME};
+use crate::resources::{ChunkManager, DropRng, GameStats};
+use crate::scoring::ZombieKilled;
+use crate::systems::chunk::{chunk_origin, tile_to_chunk, world_to_tile};
+use crate::weapons::Arsenal;
+
+/// Radius of the sensor the gaucho has to touch to take a pickup.
+const PICKUP_RADIUS: f32 = 5.;
+/// Seconds before a pickup is gone that it starts blinking.
+const BLINK_TIME: f32 = 5.;
+
+/// Spawns `saved` as a child of the tilemap of the chunk at `chunk_pos`.
+pub fn spawn_pickup(
+    commands: &mut Commands,
+    tilemap: Entity,
+    chunk_pos: IVec2,
+    saved: SavedPickup,
+) {
+    let definition = saved.kind.definition();
+    let mut lifetime = Timer::from_seconds(PICKUP_LIFETIME, TimerMode::Once);
+    lifetime.set_elapsed(Duration::from_secs_f32(
+        (PICKUP_LIFETIME - saved.remaining).max(0.),
+    ));
+    let pickup = commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: definition.color,
+                custom_size: Some(definition.size),
+                ..default()
+            },
+            // Over the blood stains, under everything standing.
+            transform: Transform::from_translation(saved.offset.extend(0.6)),
+            ..default()
+        })
+        .insert(Name::new(definition.name))
+        .insert(RigidBody::Fixed)
+        .insert(Collider::ball(PICKUP_RADIUS))
+        .insert(Sensor)
+        // The gaucho is kinematic, which fixed sensors don't see otherwise.
+        .insert(ActiveCollisionTypes::default() | ActiveCollisionTypes::KINEMATIC_STATIC)
+        .insert(Pickup {
+            kind: saved.kind,
+            chunk: chunk_pos,
+            lifetime,
+        })
+        .id();
+    commands.entity(tilemap).add_child(pickup);
+}
+
+/// Drops a `kind` pickup at `world_pos`, straight into the chunk's delta if
+/// the chunk isn't loaded.
+fn drop_pickup(
+    commands: &mut Commands,
+    chunk_manager: &mut ChunkManager,
+    world_pos: Vec2,
+    kind: PickupKind,
+) {
+    let chunk_pos = tile_to_chunk(world_to_tile(world_pos));
+    let saved = SavedPickup {
+        kind,
+        offset: world_pos - chunk_origin(chunk_pos),
+        remaining: PICKUP_LIFETIME,
+    };
+    match chunk_manager.spawned_chunks.get(&chunk_pos) {
+        Some(chunk) => spawn_pickup(commands, chunk.tilemap, chunk_pos, saved),
+        None => chunk_manager.delta_mut(chunk_pos).pickups.push(saved),
+    }
+}
+
+/// Rolls the drop table of each zombie killed.
+pub fn drop_pickups(
+    mut commands: Commands,
+    mut killed: EventReader<ZombieKilled>,
+    gaucho: Query<&Arsenal, With<Gaucho>>,
+    mut random: ResMut<DropRng>,
+    mut chunk_manager: ResMut<ChunkManager>,
+) {
+    let arsenal = gaucho.single();
+    for event in killed.iter() {
+        let Some(kind) = event.kind.archetype().drops.roll(&mut random.0) else {
+            continue;
+        };
+        let kind = kind.for_arsenal(arsenal);
+        drop_pickup(&mut commands, &mut chunk_manager, event.position, kind);
+    }
+}
+
+/// Gives the gaucho what he touches.
+pub fn collect_pickups(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    mut gaucho: Query<(Entity, &mut Health, &mut Arsenal, &mut PowerUps), With<Gaucho>>,
+    pickups: Query<(Entity, &Pickup)>,
+    mut stats: ResMut<GameStats>,
+) {
+    let (gaucho, mut health, mut arsenal, mut power_ups) = gaucho.single_mut();
+    for (entity, pickup) in pickups.iter() {
+        if rapier_context.intersection_pair(entity, gaucho) != Some(true) {
+            continue;
+        }
+        let definition = pickup.kind.definition();
+        match definition.effect {
+            PickupEffect::Heal(amount) => {
+                health.current = (health.current + amount).min(health.max);
+            }
+            PickupEffect::PowerUp(kind) => power_ups.start(kind),
+            PickupEffect::Currency(amount) => stats.coins += amount,
+            PickupEffect::Weapon => {
+                if let Some(weapon) = arsenal.next_locked() {
+                    arsenal.unlock(weapon);
+                    info!("Picked up the {}", weapon.definition().name);
+                }
+            }
+        }
+        debug!("Picked up {}", definition.name);
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Clears the pickups left on the ground too long, blinking them before
+/// they're gone.
+pub fn expire_pickups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pickups: Query<(Entity, &mut Pickup, &mut Visibility)>,
+) {
+    for (entity, mut pickup, mut visibility) in pickups.iter_mut() {
+        if pickup.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let remaining = pickup.lifetime.remaining_secs();
+        visibility.is_visible = remaining > BLINK_TIME || remaining.fract() > 0.5;
+    }
+}
+
+pub fn wear_off_power_ups(time: Res<Time>, mut power_ups: Query<&mut PowerUps>) {
+    for mut power_ups in power_ups.iter_mut() {
+        if !power_ups.0.is_empty() {
+            power_ups.tick(time.delta_seconds());
+        }
+    }
+}
+
+/// Writes the pickups of the chunks being unloaded into their deltas.
+pub fn store_pickups(
+    chunk_manager: &mut ChunkManager,
+    pickups: &Query<(&Pickup, &Transform)>,
+    unloaded: &[IVec2],
+) {
+    for (pickup, transform) in pickups.iter() {
+        if unloaded.contains(&pickup.chunk) {
+            chunk_manager
+                .delta_mut(pickup.chunk)
+                .pickups
+                .push(pickup.saved(transform));
+        }
+    }
+}
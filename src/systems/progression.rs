@@ -0,0 +1,154 @@
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+use crate::assets::{DataAssets, FontAssets};
+use crate::components::{Gaucho, Health, YSort};
+use crate::progression::{
+    Effect, Experience, ExperienceGem, UpgradeChosen, UpgradeOffer, UpgradeTable, Upgrades,
+};
+use crate::resources::UpgradeRng;
+use crate::scoring::ZombieKilled;
+use crate::systems::menus::{spawn_button, spawn_screen, text, MenuButton};
+use crate::weapons::Arsenal;
+use crate::GameState;
+
+/// Pixels per second a gem flies at once it's pulled in.
+const GEM_SPEED: f32 = 160.;
+/// Gems this close to the gaucho are picked up.
+const PICKUP_RADIUS: f32 = 6.;
+const GEM_COLOR: Color = Color::rgb(0.3, 0.9, 1.0);
+
+#[derive(Component)]
+pub struct LevelUpScreen;
+
+/// Leaves a gem where each zombie died, worth its kind's experience.
+pub fn drop_experience(mut commands: Commands, mut killed: EventReader<ZombieKilled>) {
+    for event in killed.iter() {
+        commands
+            .spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: GEM_COLOR,
+                    custom_size: Some(Vec2::splat(3.)),
+                    ..default()
+                },
+                transform: Transform::from_translation(event.position.extend(1.))
+                    .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+                ..default()
+            })
+            .insert(ExperienceGem {
+                experience: event.kind.archetype().experience,
+            })
+            .insert(YSort);
+    }
+}
+
+/// Pulls in the gems within the gaucho's magnet radius and picks up the ones
+/// that reach him.
+pub fn collect_experience(
+    mut commands: Commands,
+    time: Res<Time>,
+    data_assets: Res<DataAssets>,
+    upgrade_tables: Res<Assets<UpgradeTable>>,
+    mut gaucho: Query<(&Transform, &Upgrades, &mut Experience), With<Gaucho>>,
+    mut gems: Query<(Entity, &mut Transform, &ExperienceGem), Without<Gaucho>>,
+) {
+    let (gaucho_transform, upgrades, mut experience) = gaucho.single_mut();
+    let gaucho_position = gaucho_transform.translation.xy();
+    let table = upgrade_tables.get(&data_assets.upgrades).unwrap();
+    let radius = upgrades.stats(table).magnet_radius;
+    for (entity, mut transform, gem) in gems.iter_mut() {
+        let to_gaucho = gaucho_position - transform.translation.xy();
+        let distance = to_gaucho.length();
+        if distance <= PICKUP_RADIUS {
+            experience.gain(gem.experience);
+            commands.entity(entity).despawn();
+        } else if distance <= radius {
+            let step = (GEM_SPEED * time.delta_seconds()).min(distance);
+            transform.translation += (to_gaucho / distance * step).extend(0.);
+        }
+    }
+}
+
+/// Stops the run to pick an upgrade for each level gained.
+pub fn offer_level_ups(
+    gaucho: Query<&Experience, With<Gaucho>>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if gaucho.single().pending > 0 {
+        // Left for next frame if the gaucho died this one.
+        let _ = state.push(GameState::LevelUp);
+    }
+}
+
+pub fn roll_upgrades(
+    data_assets: Res<DataAssets>,
+    upgrade_tables: Res<Assets<UpgradeTable>>,
+    gaucho: Query<(&Upgrades, &Arsenal), With<Gaucho>>,
+    mut random: ResMut<UpgradeRng>,
+    mut offer: ResMut<UpgradeOffer>,
+) {
+    let (upgrades, arsenal) = gaucho.single();
+    let table = upgrade_tables.get(&data_assets.upgrades).unwrap();
+    offer.0 = upgrades.offer(table, arsenal, &mut random.0);
+}
+
+pub fn spawn_level_up_screen(
+    mut commands: Commands,
+    fonts: Res<FontAssets>,
+    data_assets: Res<DataAssets>,
+    upgrade_tables: Res<Assets<UpgradeTable>>,
+    offer: Res<UpgradeOffer>,
+    gaucho: Query<&Experience, With<Gaucho>>,
+) {
+    // The first of the levels still waiting for their upgrade.
+    let experience = gaucho.single();
+    let level = experience.level + 1 - experience.pending;
+    let table = upgrade_tables.get(&data_assets.upgrades).unwrap();
+    spawn_screen(&mut commands, LevelUpScreen).with_children(|parent| {
+        parent.spawn(text(&fonts, format!("LEVEL {level}"), 64.0));
+        for &upgrade in &offer.0 {
+            let definition = table.definition(upgrade);
+            spawn_button(
+                parent,
+                &fonts,
+                &definition.name,
+                MenuButton::Upgrade(upgrade),
+            );
+            parent.spawn(text(&fonts, definition.description.as_str(), 20.0));
+        }
+    });
+}
+
+/// Applies the upgrade picked and goes back to the run.
+pub fn apply_upgrade(
+    mut chosen: EventReader<UpgradeChosen>,
+    data_assets: Res<DataAssets>,
+    upgrade_tables: Res<Assets<UpgradeTable>>,
+    mut gaucho: Query<(&mut Upgrades, &mut Experience, &mut Health, &mut Arsenal), With<Gaucho>>,
+    mut state: ResMut<State<GameState>>,
+) {
+    // Read to the end, so a stray second click isn't taken for the next pick.
+    let Some(&UpgradeChosen(upgrade)) = chosen.iter().last() else {
+        return;
+    };
+    let (mut upgrades, mut experience, mut health, mut arsenal) = gaucho.single_mut();
+    let table = upgrade_tables.get(&data_assets.upgrades).unwrap();
+    match table.definition(upgrade).effect {
+        Effect::MaxHealth(bonus) => {
+            health.max += bonus;
+            health.current += bonus;
+        }
+        Effect::Weapon => {
+            if let Some(weapon) = arsenal.next_locked() {
+                arsenal.unlock(weapon);
+            }
+        }
+        Effect::Heal(amount) => {
+            health.current = (health.current + amount).min(health.max);
+        }
+        _ => {}
+    }
+    upgrades.0.push(upgrade);
+    experience.pending -= 1;
+    let _ = state.pop();
+}
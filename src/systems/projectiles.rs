@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::animations::AnimationBundle;
+use crate::components::{Damage, Knockback, Projectile, YSort};
+use crate::resources::BulletTimer;
+
+/// Pixels between the shooter and the point a projectile is spawned at, so
+/// it doesn't start inside the shooter's collider.
+const MUZZLE_OFFSET: f32 = 8.;
+
+/// Spawns `sprite` as a projectile flying from the sprite's transform along
+/// the projectile's velocity. Its hits go through `check_collisions`.
+pub fn spawn_projectile(
+    commands: &mut Commands,
+    mut sprite: AnimationBundle,
+    projectile: Projectile,
+    hitbox: Collider,
+    damage: usize,
+    knockback: f32,
+) -> Entity {
+    sprite.sprite.transform.translation +=
+        (projectile.velocity.normalize_or_zero() * MUZZLE_OFFSET).extend(0.);
+    commands
+        .spawn(sprite)
+        .insert(RigidBody::KinematicPositionBased)
+        .insert(hitbox)
+        .insert(Sensor)
+        // The gaucho is kinematic too, and would otherwise never touch
+        // projectiles shot at him.
+        .insert(ActiveCollisionTypes::default() | ActiveCollisionTypes::KINEMATIC_KINEMATIC)
+        .insert(Damage(damage))
+        .insert(Knockback(knockback))
+        .insert(projectile)
+        .insert(YSort)
+        .id()
+}
+
+/// Moves the projectiles in fixed [`BulletTimer`] steps and drops the ones
+/// that flew their whole range or outlived their lifetime.
+pub fn move_projectiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut timer: ResMut<BulletTimer>,
+    mut projectiles: Query<(Entity, &mut Transform, &mut Projectile)>,
+) {
+    timer.0.tick(time.delta());
+    let steps = timer.0.times_finished_this_tick();
+    let step = timer.0.duration().as_secs_f32() * steps as f32;
+    for (entity, mut transform, mut projectile) in projectiles.iter_mut() {
+        let delta = projectile.velocity * step;
+        transform.translation += delta.extend(0.);
+        projectile.range -= delta.length();
+        projectile.lifetime.tick(time.delta());
+        if projectile.range <= 0. || projectile.lifetime.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
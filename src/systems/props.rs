@@ -0,0 +1,145 @@
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::assets::ImageAssets;
+use crate::components::{Gaucho, Health, YSort};
+use crate::props::{Prop, PropPlacement, Supplies};
+use crate::resources::ChunkManager;
+use crate::systems::chunk::{chunk_origin, tile_to_world};
+
+/// Depth of whatever stands at height 0. Everything y-sorted stays well in
+/// front of the ground and behind the camera.
+const Y_SORT_BASE: f32 = 500.;
+/// Depth lost per pixel north.
+const Y_SORT_SCALE: f32 = 0.001;
+/// How close the gaucho has to get to supplies to take them.
+const LOOT_REACH: f32 = 16.;
+
+/// Depth of a sprite standing at height `y`.
+pub fn y_sorted_z(y: f32) -> f32 {
+    Y_SORT_BASE - y * Y_SORT_SCALE
+}
+
+/// Spawns `prop`, the `index`th of the chunk at `chunk_pos`, as a child of
+/// the chunk's tilemap. Its parts are sorted by the prop's anchor, so a tree
+/// top covers whoever walks behind the trunk.
+pub fn spawn_prop(
+    commands: &mut Commands,
+    image_assets: &ImageAssets,
+    tilemap: Entity,
+    chunk_pos: IVec2,
+    index: usize,
+    prop: PropPlacement,
+    looted: bool,
+) {
+    let definition = prop.kind.definition();
+    let anchor = tile_to_world(prop.anchor);
+    let entity = commands
+        .spawn(SpatialBundle::from_transform(Transform::from_translation(
+            (anchor - chunk_origin(chunk_pos)).extend(y_sorted_z(anchor.y)),
+        )))
+        .insert(Name::new(definition.name))
+        .insert(Prop {
+            kind: prop.kind,
+            chunk: chunk_pos,
+            index,
+            anchor: prop.anchor,
+        })
+        .with_children(|parent| {
+            for part in definition.parts {
+                let offset = tile_to_world(part.offset);
+                let mut sprite = parent.spawn(SpriteSheetBundle {
+                    texture_atlas: image_assets.tile_atlas.clone(),
+                    sprite: TextureAtlasSprite::new(part.texture as usize),
+                    transform: Transform::from_translation(offset.extend(0.)),
+                    ..default()
+                });
+                if let Some(half_extents) = part.collider {
+                    // Colliders hug the bottom of the tile, where the part
+                    // meets the ground.
+                    sprite.with_children(|part_entity| {
+                        part_entity
+                            .spawn(TransformBundle::from(Transform::from_xyz(
+                                0.,
+                                half_extents.y - 8.,
+                                0.,
+                            )))
+                            .insert(RigidBody::Fixed)
+                            .insert(Collider::cuboid(half_extents.x, half_extents.y));
+                    });
+                }
+                if let Some(supplies) = part.supplies.filter(|_| !looted) {
+                    sprite.insert(supplies);
+                }
+            }
+        })
+        .id();
+    commands.entity(tilemap).add_child(entity);
+}
+
+/// Keeps the depth of moving [`YSort`] sprites in step with their height.
+pub fn y_sort(mut sprites: Query<&mut Transform, With<YSort>>) {
+    for mut transform in sprites.iter_mut() {
+        let z = y_sorted_z(transform.translation.y);
+        if transform.translation.z != z {
+            transform.translation.z = z;
+        }
+    }
+}
+
+/// Heals the gaucho from the [`Supplies`] he reaches, which are used up for
+/// good.
+pub fn loot_supplies(
+    mut commands: Commands,
+    supplies: Query<(Entity, &GlobalTransform, &Supplies, &Parent)>,
+    props: Query<&Prop>,
+    mut gaucho: Query<(&Transform, &mut Health), With<Gaucho>>,
+    mut chunk_manager: ResMut<ChunkManager>,
+) {
+    let (gaucho_transform, mut health) = gaucho.single_mut();
+    for (entity, transform, stash, parent) in supplies.iter() {
+        if transform
+            .translation()
+            .xy()
+            .distance(gaucho_transform.translation.xy())
+            <= LOOT_REACH
+        {
+            health.current = (health.current + stash.health).min(health.max);
+            info!("The gaucho takes the supplies left at the pulpería");
+            commands.entity(entity).remove::<Supplies>();
+            if let Ok(prop) = props.get(parent.get()) {
+                chunk_manager
+                    .delta_mut(prop.chunk)
+                    .looted_props
+                    .insert(prop.index);
+            }
+        }
+    }
+}
+
+/// Breaks down the breakable props within `radius` of `center`, for good.
+pub fn break_props(
+    commands: &mut Commands,
+    chunk_manager: &mut ChunkManager,
+    props: &Query<(Entity, &GlobalTransform, &Prop)>,
+    center: Vec2,
+    radius: f32,
+) {
+    for (entity, transform, prop) in props.iter() {
+        if prop.kind.definition().breakable
+            && transform.translation().xy().distance(center) <= radius
+        {
+            chunk_manager
+                .delta_mut(prop.chunk)
+                .destroyed_props
+                .insert(prop.index);
+            let opened: Vec<IVec2> = prop.kind.definition().solid_tiles(prop.anchor).collect();
+            if let Some(chunk) = chunk_manager.spawned_chunks.get_mut(&prop.chunk) {
+                chunk.solid.retain(|tile| !opened.contains(tile));
+            }
+            chunk_manager.opened_tiles.extend(opened);
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+use crate::animations::AnimationDefinition;
+use crate::archetypes::ZombieKind;
+use crate::assets::AnimationAssets;
+use crate::components::{Gaucho, Health, Zombie};
+use crate::pickups::{Pickup, PowerUps};
+use crate::progression::{Experience, Upgrades};
+use crate::resources::{ChunkManager, GameStats, WorldSeed};
+use crate::save::{SaveGame, SavedGaucho, SavedZombie, SAVE_VERSION};
+use crate::status::StatusEffects;
+use crate::systems::pickups::store_pickups;
+use crate::systems::zombies::spawn_zombie;
+use crate::waves::{WaveDirector, WaveTable};
+use crate::weapons::Arsenal;
+
+/// Asks for the session to be saved, from F5 or the pause menu.
+pub struct SaveRequested;
+
+pub fn save_on_input(keys: Res<Input<KeyCode>>, mut requests: EventWriter<SaveRequested>) {
+    if keys.just_pressed(KeyCode::F5) {
+        requests.send(SaveRequested);
+    }
+}
+
+/// Saves the session to [`SaveGame::PATH`] when a [`SaveRequested`] comes in.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn save_game(
+    mut requests: EventReader<SaveRequested>,
+    seed: Res<WorldSeed>,
+    gaucho: Query<
+        (
+            &Transform,
+            &Health,
+            &Arsenal,
+            &Experience,
+            &Upgrades,
+            &PowerUps,
+            &StatusEffects,
+        ),
+        With<Gaucho>,
+    >,
+    zombies: Query<(&ZombieKind, &Transform, &Health, &StatusEffects), With<Zombie>>,
+    director: Res<WaveDirector>,
+    stats: Res<GameStats>,
+    chunk_manager: Res<ChunkManager>,
+    pickups: Query<(&Pickup, &Transform)>,
+) {
+    if requests.iter().count() == 0 {
+        return;
+    }
+    let Ok((gaucho_transform, gaucho_health, arsenal, experience, upgrades, power_ups, statuses)) =
+        gaucho.get_single()
+    else {
+        return;
+    };
+    // The pickups on loaded chunks are kept the way they would be if the
+    // chunks were unloaded.
+    let mut chunks = ChunkManager {
+        deltas: chunk_manager.deltas.clone(),
+        ..default()
+    };
+    let loaded: Vec<IVec2> = chunk_manager.spawned_chunks.keys().copied().collect();
+    store_pickups(&mut chunks, &pickups, &loaded);
+    let save = SaveGame {
+        version: SAVE_VERSION,
+        seed: seed.0,
+        gaucho: SavedGaucho {
+            position: gaucho_transform.translation.xy(),
+            health: gaucho_health.current,
+            max_health: gaucho_health.max,
+            weapons: arsenal.owned.clone(),
+            equipped: arsenal.equipped,
+            experience: *experience,
+            upgrades: upgrades.clone(),
+            power_ups: power_ups.clone(),
+            statuses: statuses.clone(),
+        },
+        zombies: zombies
+            .iter()
+            // Dead already, despawning at the end of the frame.
+            .filter(|(_, _, health, _)| health.current > 0)
+            .map(|(&kind, transform, health, statuses)| SavedZombie {
+                kind,
+                position: transform.translation.xy(),
+                health: health.current,
+                max_health: health.max,
+                statuses: statuses.clone(),
+            })
+            .collect(),
+        waves: (&*director).into(),
+        stats: stats.clone(),
+        chunks,
+    };
+    match save.write(SaveGame::PATH) {
+        Ok(()) => info!("Saved the game to {}", SaveGame::PATH),
+        Err(error) => warn!("Couldn't save the game to {}: {error}", SaveGame::PATH),
+    }
+}
+
+/// Brings back what `save` holds besides the gaucho and the seed: the
+/// zombies, the waves, the stats and the changed chunks.
+pub fn restore_session(
+    commands: &mut Commands,
+    save: &SaveGame,
+    animation_assets: &AnimationAssets,
+    animations: &Assets<AnimationDefinition>,
+    waves: &WaveTable,
+) {
+    let director = save.waves.director(waves);
+    for zombie in &save.zombies {
+        let animation = (zombie.kind.archetype().animation)(animation_assets);
+        let entity = spawn_zombie(
+            commands,
+            animations.get(animation).unwrap(),
+            zombie.position,
+            zombie.kind,
+            &director.definition,
+            Some(Health {
+                current: zombie.health,
+                max: zombie.max_health,
+            }),
+        );
+        commands.entity(entity).insert(zombie.statuses.clone());
+    }
+    commands.insert_resource(director);
+    commands.insert_resource(save.stats.clone());
+    commands.insert_resource(ChunkManager {
+        deltas: save.chunks.deltas.clone(),
+        ..default()
+    });
+}
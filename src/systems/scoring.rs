@@ -0,0 +1,43 @@
+use bevy::prelude::*;
+
+use crate::resources::{GameStats, WorldSeed};
+use crate::scoring::{Combo, HighScore, HighScores, ZombieKilled};
+
+/// Counts each kill and scores it by its zombie's points, times the combo
+/// multiplier.
+pub fn score_kills(
+    time: Res<Time>,
+    mut killed: EventReader<ZombieKilled>,
+    mut combo: ResMut<Combo>,
+    mut stats: ResMut<GameStats>,
+) {
+    // Ticked past change detection, so the combo only counts as changed when
+    // it breaks or grows, not every frame.
+    if combo.kills > 0 && combo.bypass_change_detection().tick(time.delta()) {
+        combo.set_changed();
+    }
+    for event in killed.iter() {
+        debug!(
+            "{} killed with the {} at {}",
+            event.kind.archetype().name,
+            event.weapon.definition().name,
+            event.position
+        );
+        stats.kills += 1;
+        stats.score += combo.kill(event.kind.archetype().points);
+    }
+}
+
+pub fn record_high_score(
+    stats: Res<GameStats>,
+    seed: Res<WorldSeed>,
+    mut high_scores: ResMut<HighScores>,
+) {
+    high_scores.record(HighScore {
+        score: stats.score,
+        kills: stats.kills,
+        waves_cleared: stats.waves_cleared,
+        survival_time: stats.survival_time,
+        seed: seed.0,
+    });
+}
@@ -0,0 +1,130 @@
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+use crate::archetypes::ZombieKind;
+use crate::components::{Gaucho, Health, Zombie};
+use crate::resources::{ChunkManager, GameStats};
+use crate::scoring::ZombieKilled;
+use crate::status::{Hazard, StatusEffects, StatusKind};
+use crate::systems::zombies::{health_bar_size, kill_zombie};
+use crate::terrain::Terrain;
+
+/// Seconds the ground keeps burning after an exploder blows up.
+const FIRE_LIFETIME: f32 = 4.;
+
+/// Leaves the ground burning within `radius` pixels of `center`.
+pub fn spawn_fire(commands: &mut Commands, center: Vec2, radius: f32) {
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1., 0.45, 0.1, 0.45),
+                custom_size: Some(Vec2::splat(radius * 2.)),
+                ..default()
+            },
+            // Over the ground and its stains, under everything standing.
+            transform: Transform::from_translation(center.extend(0.7)),
+            ..default()
+        })
+        .insert(Name::new("Fire"))
+        .insert(Hazard {
+            status: StatusKind::Burn,
+            radius,
+            lifetime: Timer::from_seconds(FIRE_LIFETIME, TimerMode::Once),
+        });
+}
+
+/// Applies what the ground does to everyone standing on it.
+pub fn apply_ground_statuses(
+    terrain: Res<Terrain>,
+    mut standing: Query<(&Transform, &mut StatusEffects)>,
+) {
+    for (transform, mut statuses) in standing.iter_mut() {
+        if let Some(status) = terrain.status_at(transform.translation.xy()) {
+            statuses.apply(status, None);
+        }
+    }
+}
+
+/// Applies the [`Hazard`]s to everyone within their reach, and clears the
+/// ones that ran out.
+pub fn apply_hazards(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut hazards: Query<(Entity, &Transform, &mut Hazard)>,
+    mut standing: Query<(&Transform, &mut StatusEffects)>,
+) {
+    for (entity, hazard_transform, mut hazard) in hazards.iter_mut() {
+        if hazard.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let center = hazard_transform.translation.xy();
+        for (transform, mut statuses) in standing.iter_mut() {
+            if transform.translation.xy().distance(center) <= hazard.radius {
+                statuses.apply(hazard.status, None);
+            }
+        }
+    }
+}
+
+/// Wears off the status effects and deals their damage over time.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn tick_status_effects(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut gaucho: Query<(&mut Health, &mut StatusEffects), With<Gaucho>>,
+    mut zombies: Query<
+        (
+            Entity,
+            &ZombieKind,
+            &Transform,
+            &mut Health,
+            &mut StatusEffects,
+            &Children,
+        ),
+        (With<Zombie>, Without<Gaucho>),
+    >,
+    mut bars: Query<&mut Sprite>,
+    mut stats: ResMut<GameStats>,
+    mut chunk_manager: ResMut<ChunkManager>,
+    mut killed: EventWriter<ZombieKilled>,
+) {
+    let seconds = time.delta_seconds();
+    let (mut health, mut statuses) = gaucho.single_mut();
+    let damage = statuses.tick(seconds).min(health.current);
+    // Unlike hits, this gets through his invulnerability.
+    health.current -= damage;
+    stats.damage_taken += damage;
+
+    for (zombie, &kind, transform, mut health, mut statuses, children) in zombies.iter_mut() {
+        // Killed by something else this frame.
+        if health.current == 0 {
+            continue;
+        }
+        // Asked before the tick, which may wear the effect off.
+        let killer = statuses.killer();
+        let damage = statuses.tick(seconds);
+        if damage == 0 {
+            continue;
+        }
+        if health.current <= damage {
+            health.current = 0;
+            kill_zombie(
+                &mut commands,
+                &mut chunk_manager,
+                &mut killed,
+                zombie,
+                kind,
+                transform.translation.xy(),
+                killer,
+            );
+            continue;
+        }
+        health.current -= damage;
+        for &child in children.iter() {
+            if let Ok(mut bar) = bars.get_mut(child) {
+                bar.custom_size = Some(health_bar_size(&health));
+            }
+        }
+    }
+}
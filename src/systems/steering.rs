@@ -0,0 +1,142 @@
+use std::f32::consts::TAU;
+
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+use crate::components::{Gaucho, Zombie};
+use crate::flow_field::FlowField;
+use crate::resources::{ChunkManager, Noise, SpatialHash};
+use crate::systems::chunk::world_to_tile;
+use crate::terrain::Terrain;
+
+/// Zombies closer than this push each other apart.
+const SEPARATION_RADIUS: f32 = 20.;
+const SEPARATION_WEIGHT: f32 = 1.2;
+/// How far ahead, in pixels, a zombie looks for blocking tiles.
+const AVOIDANCE_PROBE: f32 = 16.;
+const AVOIDANCE_ANGLE: f32 = 0.6;
+/// Zombies further than this, or with a blocking tile in between, lose sight
+/// of the gaucho and wander instead.
+pub const SIGHT_RANGE: f32 = 320.;
+const SIGHT_STEP: f32 = 8.;
+/// How much a wandering zombie still drifts toward the gaucho, relative to
+/// where it wanders.
+const WANDER_PULL: f32 = 1.2;
+/// Zoom of the noise wandering headings are read from.
+const WANDER_SCALE: f64 = 0.01;
+
+/// Tiles the flow field spreads to per frame. Enough to cover everything
+/// loaded in one go, so it is only a cap for bad frames.
+const FLOW_FIELD_BUDGET: usize = 4096;
+
+/// Keeps the [`FlowField`] in step with the gaucho's tile and the chunks in
+/// the [`ChunkManager`].
+pub fn update_flow_field(
+    mut field: ResMut<FlowField>,
+    mut chunk_manager: ResMut<ChunkManager>,
+    gaucho: Query<&Transform, With<Gaucho>>,
+) {
+    let goal = world_to_tile(gaucho.single().translation.xy());
+    if field.goal() != Some(goal) {
+        field.retarget(goal);
+    }
+    // Sorted, so the field grows the same way on every run.
+    let mut unloaded: Vec<IVec2> = field
+        .chunks()
+        .filter(|chunk_pos| !chunk_manager.spawned_chunks.contains_key(chunk_pos))
+        .collect();
+    unloaded.sort_by_key(|chunk_pos| (chunk_pos.y, chunk_pos.x));
+    for chunk_pos in unloaded {
+        field.unload_chunk(chunk_pos);
+    }
+    let mut loaded: Vec<IVec2> = chunk_manager
+        .spawned_chunks
+        .keys()
+        .copied()
+        .filter(|&chunk_pos| !field.has_chunk(chunk_pos))
+        .collect();
+    loaded.sort_by_key(|chunk_pos| (chunk_pos.y, chunk_pos.x));
+    for chunk_pos in loaded {
+        let solid = chunk_manager.spawned_chunks[&chunk_pos]
+            .solid
+            .iter()
+            .copied();
+        field.load_chunk(chunk_pos, solid);
+    }
+    // Checked first, so the manager isn't marked as changed every frame.
+    if !chunk_manager.opened_tiles.is_empty() {
+        for tile in chunk_manager.opened_tiles.drain(..) {
+            field.open_tile(tile);
+        }
+    }
+    field.advance(FLOW_FIELD_BUDGET);
+}
+
+/// Files every zombie under its cell, for the neighbor lookups of this frame.
+pub fn update_spatial_hash(
+    mut hash: ResMut<SpatialHash>,
+    zombies: Query<(Entity, &Transform), With<Zombie>>,
+) {
+    hash.clear();
+    for (entity, transform) in zombies.iter() {
+        hash.insert(entity, transform.translation.xy());
+    }
+}
+
+/// Pushes away from the zombies crowding `position`, harder the closer they
+/// are.
+pub fn separation(hash: &SpatialHash, entity: Entity, position: Vec2) -> Vec2 {
+    hash.neighbors(position, SEPARATION_RADIUS)
+        .filter(|(other, _)| *other != entity)
+        .map(|(_, other)| {
+            let away = position - other;
+            let distance = away.length();
+            if distance < 0.001 {
+                // Right on top of each other, their colliders split them up.
+                Vec2::ZERO
+            } else {
+                away / distance * (1. - distance / SEPARATION_RADIUS)
+            }
+        })
+        .sum::<Vec2>()
+        * SEPARATION_WEIGHT
+}
+
+/// Turns `heading` away from the blocking tiles right ahead of `position`.
+pub fn avoidance(terrain: &Terrain, position: Vec2, heading: Vec2) -> Vec2 {
+    let heading = heading.normalize_or_zero();
+    if heading == Vec2::ZERO || !terrain.is_solid_at(position + heading * AVOIDANCE_PROBE) {
+        return heading;
+    }
+    let left = Vec2::from_angle(AVOIDANCE_ANGLE).rotate(heading);
+    let right = Vec2::from_angle(-AVOIDANCE_ANGLE).rotate(heading);
+    match (
+        terrain.is_solid_at(position + left * AVOIDANCE_PROBE),
+        terrain.is_solid_at(position + right * AVOIDANCE_PROBE),
+    ) {
+        (false, _) => left,
+        (true, false) => right,
+        // Boxed in ahead, slide along the wall.
+        (true, true) => heading.perp(),
+    }
+}
+
+/// Whether a zombie at `from` can see the gaucho at `to`.
+pub fn can_see(terrain: &Terrain, from: Vec2, to: Vec2) -> bool {
+    let distance = from.distance(to);
+    if distance > SIGHT_RANGE {
+        return false;
+    }
+    let steps = (distance / SIGHT_STEP) as usize;
+    (1..steps).all(|step| !terrain.is_solid_at(from.lerp(to, step as f32 / steps as f32)))
+}
+
+/// A slowly turning heading for a zombie at `position` that can't see the
+/// gaucho, drifting vaguely `toward` him. It is read off the world noise, so it
+/// needs no state, replays the same with the same seed, and zombies close
+/// together shamble the same way.
+pub fn wander(noise: &Noise, position: Vec2, elapsed: f32, toward: Vec2) -> Vec2 {
+    let sample = position.as_dvec2() * WANDER_SCALE;
+    let angle = noise.0.get([sample.x, sample.y + elapsed as f64 * 0.3]) as f32 * TAU;
+    (Vec2::from_angle(angle) + toward.normalize_or_zero() * WANDER_PULL).normalize_or_zero()
+}
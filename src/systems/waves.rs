@@ -0,0 +1,113 @@
+use bevy::prelude::*;
+
+use crate::animations::AnimationDefinition;
+use crate::archetypes::ZombieKind;
+use crate::assets::{AnimationAssets, DataAssets};
+use crate::components::{Gaucho, Zombie};
+use crate::resources::{GameStats, WaveRng};
+use crate::settings::GameSettings;
+use crate::systems::chunk::world_to_tile;
+use crate::systems::zombies::{spawn_point, spawn_zombie};
+use crate::terrain::Terrain;
+use crate::waves::{WaveCleared, WaveDirector, WavePhase, WaveStarted, WaveTable};
+
+/// Moves the [`WaveDirector`] through rest, spawning and fighting, bringing in
+/// the zombies of the current wave around the gaucho, each of a kind that
+/// roams the biome it turns up in.
+#[allow(clippy::too_many_arguments)]
+pub fn direct_waves(
+    time: Res<Time>,
+    mut director: ResMut<WaveDirector>,
+    mut rng: ResMut<WaveRng>,
+    mut commands: Commands,
+    animation_assets: Res<AnimationAssets>,
+    animations: Res<Assets<AnimationDefinition>>,
+    data_assets: Res<DataAssets>,
+    wave_tables: Res<Assets<WaveTable>>,
+    terrain: Res<Terrain>,
+    gaucho_transform: Query<&Transform, With<Gaucho>>,
+    zombies: Query<(), With<Zombie>>,
+    mut started: EventWriter<WaveStarted>,
+    mut cleared: EventWriter<WaveCleared>,
+) {
+    let director = &mut *director;
+    match &mut director.phase {
+        WavePhase::Resting(timer) => {
+            if timer.tick(time.delta()).finished() {
+                director.wave += 1;
+                let table = wave_tables.get(&data_assets.waves).unwrap();
+                director.definition = table.wave(director.wave);
+                let mut timer =
+                    Timer::from_seconds(director.definition.spawn_interval, TimerMode::Repeating);
+                // The first zombie comes right away.
+                timer.set_elapsed(timer.duration());
+                director.phase = WavePhase::Spawning {
+                    remaining: director.definition.count,
+                    timer,
+                };
+                started.send(WaveStarted {
+                    wave: director.wave,
+                });
+            }
+        }
+        WavePhase::Spawning { remaining, timer } => {
+            timer.tick(time.delta());
+            let due = (timer.times_finished_this_tick() as usize).min(*remaining);
+            let gaucho_translation = gaucho_transform.single().translation;
+            for _ in 0..due {
+                let position = spawn_point(&mut rng, gaucho_translation, &terrain);
+                let biome = terrain.biome(world_to_tile(position));
+                let kind = director.definition.pick_kind(&mut rng.0, biome);
+                if kind == ZombieKind::Boss {
+                    info!(
+                        "A {} joins the horde in the {}",
+                        kind.archetype().name,
+                        biome.definition().name
+                    );
+                }
+                let animation = (kind.archetype().animation)(&animation_assets);
+                spawn_zombie(
+                    &mut commands,
+                    animations.get(animation).unwrap(),
+                    position,
+                    kind,
+                    &director.definition,
+                    None,
+                );
+            }
+            *remaining -= due;
+            if *remaining == 0 {
+                director.phase = WavePhase::Fighting;
+            }
+        }
+        WavePhase::Fighting => {
+            if zombies.is_empty() {
+                director.phase = WavePhase::Resting(Timer::from_seconds(
+                    director.definition.rest,
+                    TimerMode::Once,
+                ));
+                cleared.send(WaveCleared {
+                    wave: director.wave,
+                });
+            }
+        }
+    }
+}
+
+pub fn announce_waves(
+    mut started: EventReader<WaveStarted>,
+    mut cleared: EventReader<WaveCleared>,
+    mut stats: ResMut<GameStats>,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    settings: Res<GameSettings>,
+) {
+    for event in started.iter() {
+        info!("Wave {} started", event.wave);
+        audio.play_with_settings(asset_server.load("sounds/zombie.ogg"), settings.sound());
+    }
+    for event in cleared.iter() {
+        info!("Wave {} cleared", event.wave);
+        stats.waves_cleared += 1;
+    }
+}
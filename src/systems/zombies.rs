@@ -1,97 +1,227 @@
-use std::ops::Deref;
-
-use crate::animations::{Animation, AnimationBundle, ZombieAnimationResource};
-use crate::components::{Damage, Gaucho, Health, HitReaction, Zombie};
+use crate::animations::{
+    Action, Animation, AnimationBundle, AnimationDefinition, AnimationKey, Direction,
+};
+use crate::archetypes::{Behavior, ZombieKind};
+use crate::assets::AnimationAssets;
+use crate::components::{
+    BehaviorTimer, Damage, Gaucho, Health, HitEffect, HitReaction, Inflicts, Invulnerability,
+    Knockback, Projectile, Speed, YSort, Zombie,
+};
+use crate::flow_field::FlowField;
+use crate::props::Prop;
 use crate::resources;
+use crate::scoring::ZombieKilled;
+use crate::settings::GameSettings;
+use crate::status::StatusEffects;
+use crate::systems::chunk::leave_blood_stain;
+use crate::systems::projectiles::spawn_projectile;
+use crate::systems::props::break_props;
+use crate::systems::status::spawn_fire;
+use crate::systems::steering;
+use crate::terrain::Terrain;
+use crate::waves::WaveDefinition;
+use crate::weapons::WeaponKind;
 use bevy::math::{vec2, Vec3Swizzles};
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
-use rand::distributions::Uniform;
 use rand::seq::SliceRandom;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
-pub fn spawn_wave(
-    time: Res<Time>,
-    mut timer: ResMut<resources::WaveSpawnTimer>,
-    mut commands: Commands,
-    zombie_resource: Res<ZombieAnimationResource>,
-    gaucho_transform: Query<&Transform, (With<Gaucho>, Without<Camera2d>)>,
-) {
-    if timer.0.tick(time.delta()).just_finished() {
-        let mut rng = rand::thread_rng();
-        let gaucho_translation = gaucho_transform.get_single().unwrap().translation;
-        for _ in 0..5 {
-            let x = gaucho_translation.x
-                + rng.gen_range(100.0..500.0) * ([-1., 1.].choose(&mut rng)).unwrap();
-            let y = gaucho_translation.y
-                + rng.gen_range(100.0..500.0) * ([-1., 1.].choose(&mut rng)).unwrap();
-            let mut zombie_bundle =
-                Into::<AnimationBundle>::into(zombie_resource.deref().to_owned());
-            zombie_bundle.sprite.transform.translation.x = x;
-            zombie_bundle.sprite.transform.translation.y = y;
-            let bar = commands
-                .spawn(SpriteBundle {
-                    sprite: Sprite {
-                        color: Color::rgba(0.9, 0.0, 0., 0.8),
-                        custom_size: Some(Vec2::new(15.0, 2.0)),
-                        ..default()
-                    },
-                    transform: Transform::from_translation(Vec3 {
-                        x: 0.,
-                        y: 9.,
-                        z: 0.,
-                    }),
-                    ..default()
-                })
-                .id();
-            commands
-                .spawn(zombie_bundle)
-                .insert(RigidBody::Dynamic)
-                .insert(GravityScale(0.0))
-                .insert(Collider::cuboid(4.0, 8.0))
-                .insert(Velocity::linear(Vec2::ZERO))
-                .insert(LockedAxes::ROTATION_LOCKED)
-                .insert(HitReaction(Vec2::ZERO))
-                .insert(Health {
-                    current: 100,
-                    max: 100,
-                })
-                .add_child(bar)
-                .insert(Zombie);
+/// Seconds the gaucho can't be hurt again after a zombie touches him.
+pub const GAUCHO_INVULNERABILITY: f32 = 0.5;
+
+/// How many random spots to try before settling for one on a solid tile.
+const SPAWN_ATTEMPTS: usize = 8;
+
+/// Picks a random spot 100 to 500 pixels away from `around` on each axis,
+/// avoiding solid tiles when it can.
+pub fn spawn_point(rng: &mut resources::WaveRng, around: Vec3, terrain: &Terrain) -> Vec2 {
+    let mut point = Vec2::ZERO;
+    for _ in 0..SPAWN_ATTEMPTS {
+        let x = around.x + rng.gen_range(100.0..500.0) * ([-1., 1.].choose(&mut rng.0)).unwrap();
+        let y = around.y + rng.gen_range(100.0..500.0) * ([-1., 1.].choose(&mut rng.0)).unwrap();
+        point = vec2(x, y);
+        if !terrain.is_solid_at(point) {
+            break;
         }
     }
+    point
 }
 
+/// Spawns a zombie of `kind` at `position`, scaled for `wave`, with `health`
+/// as it was or else unhurt.
+pub fn spawn_zombie(
+    commands: &mut Commands,
+    animation: &AnimationDefinition,
+    position: Vec2,
+    kind: ZombieKind,
+    wave: &WaveDefinition,
+    health: Option<Health>,
+) -> Entity {
+    let archetype = kind.archetype();
+    let health = health.unwrap_or_else(|| {
+        let max = (archetype.health as f32 * wave.health_multiplier).round() as usize;
+        Health { current: max, max }
+    });
+    let mut zombie_bundle = AnimationBundle::from(animation);
+    zombie_bundle.sprite.transform.translation.x = position.x;
+    zombie_bundle.sprite.transform.translation.y = position.y;
+    zombie_bundle.sprite.transform.scale = Vec3::splat(archetype.scale);
+    let bar = commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(0.9, 0.0, 0., 0.8),
+                custom_size: Some(health_bar_size(&health)),
+                ..default()
+            },
+            transform: Transform::from_translation(Vec3 {
+                x: 0.,
+                y: 9.,
+                z: 0.,
+            }),
+            ..default()
+        })
+        .id();
+    let mut zombie = commands.spawn(zombie_bundle);
+    zombie
+        .insert(RigidBody::Dynamic)
+        .insert(GravityScale(0.0))
+        .insert(Collider::cuboid(archetype.collider.0, archetype.collider.1))
+        .insert(Velocity::linear(Vec2::ZERO))
+        .insert(LockedAxes::ROTATION_LOCKED)
+        .insert(HitReaction(Vec2::ZERO))
+        .insert(health)
+        .insert(Speed(archetype.speed * wave.speed_multiplier))
+        .insert(kind)
+        .insert(StatusEffects::default())
+        .add_child(bar)
+        .insert(YSort)
+        .insert(Zombie);
+    match archetype.behavior {
+        Behavior::Spit { cooldown, .. } => {
+            zombie.insert(BehaviorTimer(Timer::from_seconds(
+                cooldown,
+                TimerMode::Repeating,
+            )));
+        }
+        Behavior::Charge { every, .. } => {
+            zombie.insert(BehaviorTimer(Timer::from_seconds(
+                every,
+                TimerMode::Repeating,
+            )));
+        }
+        Behavior::Chase | Behavior::Explode { .. } => {}
+    }
+    zombie.id()
+}
+
+/// Size of the bar over a zombie with `health`.
+pub fn health_bar_size(health: &Health) -> Vec2 {
+    vec2(16. * health.current as f32 / health.max as f32, 2.)
+}
+
+/// Steers zombies toward the gaucho while they can see him, and around
+/// blocking tiles and each other.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn update_zombies(
     mut zombies: Query<
         (
+            Entity,
             &mut Velocity,
+            &Speed,
+            &ZombieKind,
+            Option<&mut BehaviorTimer>,
             &mut Transform,
             &mut Animation,
             &mut HitReaction,
             &mut TextureAtlasSprite,
+            &StatusEffects,
         ),
         With<Zombie>,
     >,
     gaucho: Query<&Transform, (With<Gaucho>, Without<Zombie>)>,
+    noise: Res<resources::Noise>,
+    terrain: Res<Terrain>,
+    hash: Res<resources::SpatialHash>,
+    flow_field: Res<FlowField>,
+    stats: Res<resources::GameStats>,
     time: Res<Time>,
 ) {
     let gaucho_pos = gaucho.single();
-    for (mut zombie_vel, mut zombie_pos, mut animation, mut hit_reaction, mut sprite) in
-        zombies.iter_mut()
+    for (
+        entity,
+        mut zombie_vel,
+        speed,
+        kind,
+        mut behavior_timer,
+        mut zombie_pos,
+        mut animation,
+        mut hit_reaction,
+        mut sprite,
+        statuses,
+    ) in zombies.iter_mut()
     {
-        let dir = gaucho_pos.translation - zombie_pos.translation;
-        zombie_vel.linvel = Vec2::new(dir.x, dir.y).normalize() * 50.;
-        if zombie_vel.linvel.y.abs() > zombie_vel.linvel.x.abs() {
-            if zombie_vel.linvel.y > 0. {
-                animation.set_state("UpWalking".to_string());
+        let archetype = kind.archetype();
+        let tint = statuses.tint(Color::WHITE);
+        let dir = (gaucho_pos.translation - zombie_pos.translation).truncate();
+        if let Some(timer) = behavior_timer.as_mut() {
+            timer.tick(time.delta());
+        }
+        let pace = match archetype.behavior {
+            Behavior::Chase | Behavior::Explode { .. } => 1.,
+            // Close in until in range, and back off when the gaucho gets near.
+            Behavior::Spit { range, .. } => {
+                let distance = dir.length();
+                if distance > range * 0.8 {
+                    1.
+                } else if distance < range * 0.5 {
+                    -1.
+                } else {
+                    0.
+                }
+            }
+            Behavior::Charge {
+                duration,
+                multiplier,
+                ..
+            } => {
+                let charging =
+                    matches!(&behavior_timer, Some(timer) if timer.elapsed_secs() < duration);
+                if charging {
+                    multiplier
+                } else {
+                    1.
+                }
+            }
+        };
+        let position = zombie_pos.translation.xy();
+        let seek = if statuses.stunned() {
+            Vec2::ZERO
+        } else if steering::can_see(&terrain, position, gaucho_pos.translation.xy()) {
+            dir.normalize_or_zero() * pace
+        } else if let Some(path) = flow_field.direction(position) {
+            // Nothing to keep away from yet, so spitters close in too.
+            path * pace.max(1.)
+        } else {
+            // Survival time rather than the app's, which doesn't restart with
+            // the game.
+            steering::wander(&noise, position, stats.survival_time.as_secs_f32(), dir)
+        };
+        let steer = steering::avoidance(&terrain, position, seek) * seek.length()
+            + steering::separation(&hash, entity, position);
+        // Charging speeds a zombie up past its usual pace.
+        zombie_vel.linvel =
+            steer.clamp_length_max(pace.abs().max(1.)) * speed.0 * statuses.speed_multiplier();
+        if dir.y.abs() > dir.x.abs() {
+            if dir.y > 0. {
+                animation.set_state(AnimationKey::new(Direction::Up, Action::Walking));
             } else {
-                animation.set_state("DownWalking".to_string());
+                animation.set_state(AnimationKey::new(Direction::Down, Action::Walking));
             }
-        } else if zombie_vel.linvel.x > 0. {
-            animation.set_state("RightWalking".to_string());
+        } else if dir.x > 0. {
+            animation.set_state(AnimationKey::new(Direction::Right, Action::Walking));
         } else {
-            animation.set_state("LeftWalking".to_string());
+            animation.set_state(AnimationKey::new(Direction::Left, Action::Walking));
         }
         if hit_reaction.length() > 0.001 {
             zombie_pos.translation.x += hit_reaction.0.x;
@@ -100,74 +230,334 @@ pub fn update_zombies(
             sprite.color = if time.elapsed().as_millis() % 100 < 50 {
                 Color::RED
             } else {
-                Color::WHITE
+                tint
             };
         } else {
             hit_reaction.0 = Vec2::ZERO;
-            sprite.color = Color::WHITE;
+            sprite.color = tint;
         }
     }
 }
 
+/// Spitters spit at the gaucho whenever their [`BehaviorTimer`] comes around
+/// with him in range, and exploders blow up once they reach him, breaking
+/// down the props around them.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn zombie_specials(
+    mut commands: Commands,
+    mut zombies: Query<
+        (
+            Entity,
+            &ZombieKind,
+            &Transform,
+            &mut Health,
+            Option<&BehaviorTimer>,
+            &StatusEffects,
+        ),
+        With<Zombie>,
+    >,
+    mut gaucho: Query<
+        (
+            &Transform,
+            &mut Health,
+            &mut Invulnerability,
+            &mut HitReaction,
+            &mut StatusEffects,
+        ),
+        (With<Gaucho>, Without<Zombie>),
+    >,
+    props: Query<(Entity, &GlobalTransform, &Prop)>,
+    mut stats: ResMut<resources::GameStats>,
+    mut chunk_manager: ResMut<resources::ChunkManager>,
+    animation_assets: Res<AnimationAssets>,
+    animations: Res<Assets<AnimationDefinition>>,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    settings: Res<GameSettings>,
+) {
+    let (
+        gaucho_transform,
+        mut gaucho_health,
+        mut invulnerability,
+        mut gaucho_reaction,
+        mut gaucho_statuses,
+    ) = gaucho.single_mut();
+    for (zombie, kind, transform, mut health, behavior_timer, statuses) in zombies.iter_mut() {
+        if statuses.stunned() {
+            continue;
+        }
+        let archetype = kind.archetype();
+        let to_gaucho = (gaucho_transform.translation - transform.translation).truncate();
+        match archetype.behavior {
+            Behavior::Spit {
+                range,
+                damage,
+                projectile,
+                ..
+            } => {
+                if matches!(behavior_timer, Some(timer) if timer.just_finished())
+                    && to_gaucho.length() <= range
+                {
+                    let mut spit =
+                        AnimationBundle::from(animations.get(&animation_assets.spit).unwrap());
+                    spit.sprite.transform.translation = transform.translation;
+                    let spit = spawn_projectile(
+                        &mut commands,
+                        spit,
+                        projectile.projectile(to_gaucho.normalize_or_zero(), range * 1.5, true),
+                        Collider::ball(2.),
+                        damage,
+                        2.,
+                    );
+                    commands.entity(spit).insert(Inflicts(archetype.inflicts));
+                }
+            }
+            Behavior::Explode { radius, damage } => {
+                // Holds off while the gaucho is invulnerable, rather than
+                // blowing up for nothing.
+                if to_gaucho.length() <= radius && invulnerability.finished() {
+                    hurt_gaucho(&mut gaucho_health, &mut invulnerability, &mut stats, damage);
+                    gaucho_reaction.0 += to_gaucho.normalize_or_zero() * 8.;
+                    for &status in archetype.inflicts {
+                        gaucho_statuses.apply(status, None);
+                    }
+                    audio.play_with_settings(
+                        asset_server.load("sounds/impact.ogg"),
+                        settings.sound(),
+                    );
+                    let center = transform.translation.xy();
+                    break_props(&mut commands, &mut chunk_manager, &props, center, radius);
+                    leave_blood_stain(&mut commands, &mut chunk_manager, center);
+                    spawn_fire(&mut commands, center, radius);
+                    // Dead to everything else this frame, without counting
+                    // as a kill.
+                    health.current = 0;
+                    commands.entity(zombie).despawn_recursive();
+                }
+            }
+            Behavior::Chase | Behavior::Charge { .. } => {}
+        }
+    }
+}
+
+/// Despawns a `zombie` of `kind` killed at `position`. It counts as a kill,
+/// for the score and the drops, only if it was the gaucho's `weapon`.
+pub fn kill_zombie(
+    commands: &mut Commands,
+    chunk_manager: &mut resources::ChunkManager,
+    killed: &mut EventWriter<ZombieKilled>,
+    zombie: Entity,
+    kind: ZombieKind,
+    position: Vec2,
+    weapon: Option<WeaponKind>,
+) {
+    commands.entity(zombie).despawn_recursive();
+    leave_blood_stain(commands, chunk_manager, position);
+    if let Some(weapon) = weapon {
+        killed.send(ZombieKilled {
+            weapon,
+            kind,
+            position,
+        });
+    }
+}
+
+/// Takes `damage` off the gaucho's health and makes him invulnerable for a
+/// moment.
+pub fn hurt_gaucho(
+    health: &mut Health,
+    invulnerability: &mut Invulnerability,
+    stats: &mut resources::GameStats,
+    damage: usize,
+) {
+    let damage = damage.min(health.current);
+    health.current -= damage;
+    stats.damage_taken += damage;
+    invulnerability.reset();
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn check_collisions(
     mut commands: Commands,
-    weapons: Query<(Entity, &Damage)>,
-    mut gaucho: Query<(Entity, &mut HitReaction, &Transform), With<Gaucho>>,
+    mut weapons: Query<(
+        Entity,
+        &Damage,
+        &Knockback,
+        Option<&mut Projectile>,
+        Option<&WeaponKind>,
+        Option<&Inflicts>,
+    )>,
+    mut gaucho: Query<
+        (
+            Entity,
+            &mut HitReaction,
+            &mut Health,
+            &mut Invulnerability,
+            &Transform,
+            &mut StatusEffects,
+        ),
+        With<Gaucho>,
+    >,
     mut zombies: Query<
-        (Entity, &mut HitReaction, &mut Health, &Transform, &Children),
+        (
+            Entity,
+            &mut HitReaction,
+            &mut Health,
+            &Transform,
+            &Children,
+            &mut StatusEffects,
+        ),
         (With<Zombie>, Without<Gaucho>),
     >,
+    kinds: Query<&ZombieKind>,
     mut zombie_children: Query<&mut Sprite>,
     asset_server: Res<AssetServer>,
     audio: Res<Audio>,
+    settings: Res<GameSettings>,
     rapier_context: Res<RapierContext>,
+    mut stats: ResMut<resources::GameStats>,
+    mut chunk_manager: ResMut<resources::ChunkManager>,
+    mut killed: EventWriter<ZombieKilled>,
 ) {
-    let (gaucho, mut gaucho_reaction, gaucho_transform) = gaucho.get_single_mut().unwrap();
-    if gaucho_reaction.length() == 0. {
-        for (zombie, mut zombie_reaction, _, _, _) in zombies.iter_mut() {
-            if let Some(contact_pair) = rapier_context.contact_pair(gaucho, zombie) {
-                if contact_pair.has_any_active_contacts() {
-                    for manifold in contact_pair.manifolds() {
-                        gaucho_reaction.x += manifold.local_n2().x * 5.;
-                        gaucho_reaction.y += manifold.local_n2().y * 5.;
-                        zombie_reaction.x += manifold.local_n1().x * 5.;
-                        zombie_reaction.y += manifold.local_n1().y * 5.;
+    let (
+        gaucho,
+        mut gaucho_reaction,
+        mut gaucho_health,
+        mut invulnerability,
+        gaucho_transform,
+        mut gaucho_statuses,
+    ) = gaucho.get_single_mut().unwrap();
+    let gaucho_position = gaucho_transform.translation.xy();
+    for (zombie, mut zombie_reaction, _, _, _, _) in zombies.iter_mut() {
+        // The first zombie to hurt him makes him invulnerable to the rest.
+        if !invulnerability.finished() {
+            break;
+        }
+        if let Some(contact_pair) = rapier_context.contact_pair(gaucho, zombie) {
+            if contact_pair.has_any_active_contacts() {
+                let archetype = kinds.get(zombie).unwrap().archetype();
+                if archetype.contact_damage > 0 {
+                    hurt_gaucho(
+                        &mut gaucho_health,
+                        &mut invulnerability,
+                        &mut stats,
+                        archetype.contact_damage,
+                    );
+                    for &status in archetype.inflicts {
+                        gaucho_statuses.apply(status, None);
                     }
                 }
+                for manifold in contact_pair.manifolds() {
+                    gaucho_reaction.x += manifold.local_n2().x * 5.;
+                    gaucho_reaction.y += manifold.local_n2().y * 5.;
+                    zombie_reaction.x += manifold.local_n1().x * 5.;
+                    zombie_reaction.y += manifold.local_n1().y * 5.;
+                }
             }
         }
     }
-    let mut random = thread_rng();
-
-    for (weapon, damage) in weapons.iter() {
-        for (zombie, mut zombie_reaction, mut health, zombie_transform, children) in
-            zombies.iter_mut()
-        {
-            if zombie_reaction.length() == 0. {
-                if rapier_context.intersection_pair(weapon, zombie) == Some(true) {
-                    let zombie_sound = asset_server.load("sounds/zombie.ogg");
-                    let impact = asset_server.load("sounds/impact.ogg");
 
-                    audio.play(impact);
-                    audio.play(zombie_sound);
-                    let damage = (damage.0 as f32 * random.sample(Uniform::new(0.5, 2.))) as usize;
-                    if health.current <= damage {
-                        commands.entity(zombie).despawn_recursive();
-                    } else {
-                        health.current -= damage;
-                        zombie_reaction.0 += (zombie_transform.translation.xy()
-                            - gaucho_transform.translation.xy())
-                        .normalize()
-                            * 5.;
-                        for &child in children.iter() {
-                            if let Ok(mut health_sprite) = zombie_children.get_mut(child) {
-                                health_sprite.custom_size =
-                                    Some(vec2(16. * health.current as f32 / health.max as f32, 2.))
+    // Hits are gathered first, so a splash can reach zombies other than the
+    // one the projectile touched.
+    let mut hits = Vec::new();
+    for (weapon, damage, knockback, mut projectile, weapon_kind, inflicts) in weapons.iter_mut() {
+        let inflicts = inflicts.map_or(&[][..], |inflicts| inflicts.0);
+        if let Some(projectile) = projectile.as_mut().filter(|p| p.hurts_gaucho) {
+            if rapier_context.intersection_pair(weapon, gaucho) != Some(true) {
+                continue;
+            }
+            // It splats on him either way, it just doesn't hurt.
+            if invulnerability.finished() {
+                hurt_gaucho(
+                    &mut gaucho_health,
+                    &mut invulnerability,
+                    &mut stats,
+                    damage.0,
+                );
+                gaucho_reaction.0 += projectile.velocity.normalize_or_zero() * knockback.0;
+                for &status in inflicts {
+                    gaucho_statuses.apply(status, None);
+                }
+            }
+            commands.entity(weapon).despawn_recursive();
+            continue;
+        }
+        // Only the gaucho's weapons hurt zombies.
+        let Some(&weapon_kind) = weapon_kind else {
+            continue;
+        };
+        for (zombie, zombie_reaction, _, zombie_transform, _, _) in zombies.iter() {
+            if zombie_reaction.length() != 0.
+                || matches!(&projectile, Some(projectile) if projectile.hit.contains(&zombie))
+                || rapier_context.intersection_pair(weapon, zombie) != Some(true)
+            {
+                continue;
+            }
+            hits.push((zombie, damage.0, knockback.0, weapon_kind, inflicts));
+            let Some(projectile) = projectile.as_mut() else {
+                audio.play_with_settings(asset_server.load("sounds/impact.ogg"), settings.sound());
+                continue;
+            };
+            projectile.hit.push(zombie);
+            for effect in projectile.on_hit {
+                match *effect {
+                    HitEffect::Sound(sound) => {
+                        audio.play_with_settings(asset_server.load(sound), settings.sound());
+                    }
+                    HitEffect::Splash { radius } => {
+                        let center = zombie_transform.translation.xy();
+                        for (other, other_reaction, _, other_transform, _, _) in zombies.iter() {
+                            if other != zombie
+                                && other_reaction.length() == 0.
+                                && other_transform.translation.xy().distance(center) <= radius
+                            {
+                                hits.push((other, damage.0, knockback.0, weapon_kind, inflicts));
                             }
                         }
                     }
                 }
             }
+            if projectile.hit.len() > projectile.pierce {
+                commands.entity(weapon).despawn_recursive();
+                break;
+            }
+        }
+    }
+
+    for (zombie, damage, knockback, weapon, inflicts) in hits {
+        let Ok((zombie, mut zombie_reaction, mut health, zombie_transform, children, mut statuses)) =
+            zombies.get_mut(zombie)
+        else {
+            continue;
+        };
+        // Already killed by another hit this frame.
+        if health.current == 0 {
+            continue;
+        }
+        audio.play_with_settings(asset_server.load("sounds/zombie.ogg"), settings.sound());
+        if health.current <= damage {
+            health.current = 0;
+            kill_zombie(
+                &mut commands,
+                &mut chunk_manager,
+                &mut killed,
+                zombie,
+                *kinds.get(zombie).unwrap(),
+                zombie_transform.translation.xy(),
+                Some(weapon),
+            );
+        } else {
+            health.current -= damage;
+            for &status in inflicts {
+                statuses.apply(status, Some(weapon));
+            }
+            zombie_reaction.0 +=
+                (zombie_transform.translation.xy() - gaucho_position).normalize() * knockback;
+            for &child in children.iter() {
+                if let Ok(mut health_sprite) = zombie_children.get_mut(child) {
+                    health_sprite.custom_size = Some(health_bar_size(&health));
+                }
+            }
         }
     }
 }
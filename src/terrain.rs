@@ -0,0 +1,355 @@
+use bevy::prelude::*;
+use noise::{Fbm, MultiFractal, NoiseFn, SuperSimplex};
+use rand::Rng;
+
+use crate::archetypes::ZombieKind;
+use crate::props::PropKind;
+use crate::resources::{RngStream, WorldSeed};
+use crate::status::StatusKind;
+use crate::systems::chunk::{world_to_tile, CHUNK_SIZE};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Biome {
+    Pampa,
+    Lagoon,
+    Scrub,
+    Hills,
+}
+
+/// Textures for one kind of ground.
+pub struct TileSet {
+    /// Picked at random for tiles surrounded by the same ground.
+    pub tiles: &'static [u32],
+    /// Drawn instead where the ground meets a different one, laid out as a
+    /// 3x3 patch: north-west, north, north-east, west, middle, east,
+    /// south-west, south and south-east.
+    pub border: Option<[u32; 9]>,
+}
+
+pub struct BiomeDefinition {
+    pub name: &'static str,
+    /// The walkable ground.
+    pub floor: TileSet,
+    /// The ground nothing walks through, and how deep into the biome it
+    /// starts, in noise units past the biome's own threshold.
+    pub solid: Option<(f64, TileSet)>,
+    /// Applied to whoever walks on the walkable ground, like the mud around
+    /// lagoons.
+    pub ground_status: Option<StatusKind>,
+    /// How common each kind of zombie is here, 1 being as common as
+    /// anywhere. Kinds left out never show up here.
+    pub zombies: &'static [(ZombieKind, u32)],
+    /// Chance of each kind of prop standing on a tile, tried in order until
+    /// one is placed.
+    pub props: &'static [(PropKind, f64)],
+}
+
+impl Biome {
+    pub fn definition(&self) -> &'static BiomeDefinition {
+        match self {
+            Biome::Pampa => &PAMPA,
+            Biome::Lagoon => &LAGOON,
+            Biome::Scrub => &SCRUB,
+            Biome::Hills => &HILLS,
+        }
+    }
+}
+
+impl BiomeDefinition {
+    pub fn zombie_weight(&self, kind: ZombieKind) -> u32 {
+        self.zombies
+            .iter()
+            .find(|(roaming, _)| *roaming == kind)
+            .map_or(0, |(_, weight)| *weight)
+    }
+}
+
+static PAMPA: BiomeDefinition = BiomeDefinition {
+    name: "pampa",
+    floor: TileSet {
+        tiles: &[0, 1, 2, 3, 4, 5],
+        border: None,
+    },
+    solid: None,
+    ground_status: None,
+    zombies: &[
+        (ZombieKind::Walker, 1),
+        (ZombieKind::Runner, 1),
+        (ZombieKind::Tank, 1),
+        (ZombieKind::Spitter, 1),
+        (ZombieKind::Exploder, 1),
+        (ZombieKind::Boss, 1),
+    ],
+    props: &[
+        (PropKind::Estancia, 0.002),
+        (PropKind::Well, 0.003),
+        (PropKind::Ombu, 0.015),
+        (PropKind::Fence, 0.01),
+        (PropKind::Carcass, 0.008),
+    ],
+};
+
+static LAGOON: BiomeDefinition = BiomeDefinition {
+    name: "lagoon",
+    floor: TileSet {
+        tiles: &[120, 131],
+        border: None,
+    },
+    solid: Some((
+        0.15,
+        TileSet {
+            tiles: &[98, 109],
+            border: None,
+        },
+    )),
+    ground_status: Some(StatusKind::Slow),
+    zombies: &[
+        (ZombieKind::Walker, 1),
+        (ZombieKind::Runner, 1),
+        (ZombieKind::Spitter, 3),
+    ],
+    props: &[(PropKind::Ombu, 0.006), (PropKind::Carcass, 0.004)],
+};
+
+static SCRUB: BiomeDefinition = BiomeDefinition {
+    name: "dry scrub",
+    floor: TileSet {
+        tiles: &[100],
+        border: Some([88, 89, 90, 99, 100, 101, 110, 111, 112]),
+    },
+    solid: None,
+    ground_status: None,
+    zombies: &[
+        (ZombieKind::Walker, 1),
+        (ZombieKind::Runner, 2),
+        (ZombieKind::Spitter, 1),
+        (ZombieKind::Exploder, 3),
+        (ZombieKind::Boss, 1),
+    ],
+    props: &[
+        (PropKind::Well, 0.004),
+        (PropKind::Fence, 0.004),
+        (PropKind::Carcass, 0.025),
+    ],
+};
+
+static HILLS: BiomeDefinition = BiomeDefinition {
+    name: "rocky hills",
+    floor: TileSet {
+        tiles: &[96, 107, 118],
+        border: None,
+    },
+    solid: Some((
+        0.2,
+        TileSet {
+            tiles: &[73, 74, 75],
+            border: Some([62, 63, 64, 73, 74, 75, 84, 85, 86]),
+        },
+    )),
+    ground_status: None,
+    zombies: &[
+        (ZombieKind::Walker, 1),
+        (ZombieKind::Tank, 3),
+        (ZombieKind::Exploder, 1),
+        (ZombieKind::Boss, 2),
+    ],
+    props: &[(PropKind::Carcass, 0.01)],
+};
+
+/// Elevation above which the ground is hills.
+const HILLS_ELEVATION: f64 = 0.55;
+/// Moisture above which the ground is a lagoon.
+const LAGOON_MOISTURE: f64 = 0.6;
+/// Warmer and drier than this, the ground is scrub.
+const SCRUB_TEMPERATURE: f64 = 0.15;
+const SCRUB_MOISTURE: f64 = 0.1;
+/// Noise cycles per tile. Biomes span a few dozen tiles.
+const BIOME_FREQUENCY: f64 = 1. / 48.;
+/// Tiles this close to the origin, where the gaucho starts, are never solid.
+pub const SPAWN_CLEARING: i32 = 2;
+
+/// The noise channels the terrain is generated from. Cheap enough to clone
+/// into chunk generation tasks.
+#[derive(Resource, Clone)]
+pub struct Terrain {
+    elevation: Fbm<SuperSimplex>,
+    temperature: Fbm<SuperSimplex>,
+    moisture: Fbm<SuperSimplex>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TerrainTile {
+    pub texture: u32,
+    pub solid: bool,
+}
+
+/// What a chunk's tiles are made of.
+#[derive(PartialEq, Eq, Debug)]
+pub struct ChunkTerrain {
+    /// Row by row, from the chunk's bottom left tile.
+    tiles: Vec<TerrainTile>,
+}
+
+impl ChunkTerrain {
+    pub fn get(&self, tile: UVec2) -> TerrainTile {
+        self.tiles[(tile.y * CHUNK_SIZE.x + tile.x) as usize]
+    }
+}
+
+impl Terrain {
+    pub fn new(seed: &WorldSeed) -> Self {
+        // Each octave of an Fbm takes the next seed, so channels are kept well
+        // apart.
+        let channel = |offset: u32| {
+            Fbm::<SuperSimplex>::new(seed.noise_seed().wrapping_add(offset))
+                .set_octaves(4)
+                .set_frequency(BIOME_FREQUENCY)
+        };
+        Self {
+            elevation: channel(100),
+            temperature: channel(200),
+            moisture: channel(300),
+        }
+    }
+
+    /// The biome of a tile, and how deep into it the tile is.
+    fn classify(&self, tile: IVec2) -> (Biome, f64) {
+        let point = [tile.x as f64, tile.y as f64];
+        let elevation = self.elevation.get(point);
+        if elevation > HILLS_ELEVATION {
+            return (Biome::Hills, elevation - HILLS_ELEVATION);
+        }
+        let moisture = self.moisture.get(point);
+        if moisture > LAGOON_MOISTURE {
+            return (Biome::Lagoon, moisture - LAGOON_MOISTURE);
+        }
+        if self.temperature.get(point) > SCRUB_TEMPERATURE && moisture < SCRUB_MOISTURE {
+            return (Biome::Scrub, 0.);
+        }
+        (Biome::Pampa, 0.)
+    }
+
+    /// The biome of a tile, and whether it is solid.
+    pub fn ground(&self, tile: IVec2) -> (Biome, bool) {
+        let (biome, depth) = self.classify(tile);
+        let solid = matches!(biome.definition().solid, Some((from, _)) if depth >= from)
+            && tile.abs().max_element() > SPAWN_CLEARING;
+        (biome, solid)
+    }
+
+    pub fn biome(&self, tile: IVec2) -> Biome {
+        self.classify(tile).0
+    }
+
+    /// Whether the tile at grid position `tile` is solid, whether or not its
+    /// chunk is loaded.
+    pub fn is_solid(&self, tile: IVec2) -> bool {
+        self.ground(tile).1
+    }
+
+    /// What the ground under `world_pos` does to whoever stands on it.
+    pub fn status_at(&self, world_pos: Vec2) -> Option<StatusKind> {
+        self.biome(world_to_tile(world_pos))
+            .definition()
+            .ground_status
+    }
+
+    /// Whether the tile under `world_pos` is solid.
+    pub fn is_solid_at(&self, world_pos: Vec2) -> bool {
+        self.is_solid(world_to_tile(world_pos))
+    }
+}
+
+/// Works out the tiles of the chunk at `chunk_pos`. The same seed always
+/// gives the same chunk.
+pub fn generate_chunk(terrain: &Terrain, seed: &WorldSeed, chunk_pos: IVec2) -> ChunkTerrain {
+    let mut random = seed.rng(RngStream::Chunk(chunk_pos));
+    let size = CHUNK_SIZE.as_ivec2();
+    let origin = chunk_pos * size;
+    // The chunk's ground plus a ring of its neighbors' for the borders.
+    let ring_width = size.x + 2;
+    let ground: Vec<(Biome, bool)> = (-1..=size.y)
+        .flat_map(|y| (-1..=size.x).map(move |x| IVec2::new(x, y)))
+        .map(|tile| terrain.ground(origin + tile))
+        .collect();
+    let ground_at = |tile: IVec2| ground[((tile.y + 1) * ring_width + tile.x + 1) as usize];
+
+    let mut tiles = Vec::with_capacity((size.x * size.y) as usize);
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let tile = IVec2::new(x, y);
+            let (biome, solid) = ground_at(tile);
+            let definition = biome.definition();
+            let tile_set = match &definition.solid {
+                Some((_, tile_set)) if solid => tile_set,
+                _ => &definition.floor,
+            };
+            let differs = |step: IVec2| ground_at(tile + step) != (biome, solid);
+            let (north, south) = (differs(IVec2::Y), differs(IVec2::NEG_Y));
+            let (west, east) = (differs(IVec2::NEG_X), differs(IVec2::X));
+            let texture = match tile_set.border {
+                Some(border) if north || south || west || east => {
+                    let row = if north && !south {
+                        0
+                    } else if south && !north {
+                        2
+                    } else {
+                        1
+                    };
+                    let column = if west && !east {
+                        0
+                    } else if east && !west {
+                        2
+                    } else {
+                        1
+                    };
+                    border[row * 3 + column]
+                }
+                _ => tile_set.tiles[random.gen_range(0..tile_set.tiles.len())],
+            };
+            tiles.push(TerrainTile { texture, solid });
+        }
+    }
+    ChunkTerrain { tiles }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(seed: u64, chunk_pos: IVec2) -> ChunkTerrain {
+        let seed = WorldSeed(seed);
+        generate_chunk(&Terrain::new(&seed), &seed, chunk_pos)
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_chunk() {
+        for chunk_pos in [IVec2::ZERO, IVec2::new(3, -2), IVec2::new(-40, 17)] {
+            assert_eq!(chunk(42, chunk_pos), chunk(42, chunk_pos));
+        }
+    }
+
+    #[test]
+    fn different_seeds_give_different_chunks() {
+        for chunk_pos in [IVec2::ZERO, IVec2::new(3, -2), IVec2::new(-40, 17)] {
+            assert_ne!(chunk(42, chunk_pos), chunk(43, chunk_pos));
+        }
+    }
+
+    #[test]
+    fn spawn_clearing_is_never_solid() {
+        let size = CHUNK_SIZE.as_ivec2();
+        for seed in 0..50 {
+            for chunk_pos in [IVec2::ZERO, IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+                let origin = chunk_pos * size;
+                for (index, tile) in chunk(seed, chunk_pos).tiles.iter().enumerate() {
+                    let index = index as i32;
+                    let position = origin + IVec2::new(index % size.x, index / size.x);
+                    if position.abs().max_element() <= SPAWN_CLEARING {
+                        assert!(!tile.solid, "seed {seed} has {position} solid");
+                    }
+                }
+            }
+        }
+    }
+}
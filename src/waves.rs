@@ -0,0 +1,200 @@
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::archetypes::ZombieKind;
+use crate::terrain::Biome;
+
+#[derive(Deserialize, Clone, Default, Debug)]
+pub struct WaveDefinition {
+    pub count: usize,
+    /// Kinds of zombie the wave is made of, each with its relative chance in
+    /// this wave, on top of the kind's own spawn weight.
+    pub composition: Vec<(ZombieKind, u32)>,
+    /// Seconds between two zombies of the wave.
+    pub spawn_interval: f32,
+    pub health_multiplier: f32,
+    pub speed_multiplier: f32,
+    /// Seconds of calm after the wave is cleared.
+    pub rest: f32,
+}
+
+/// The opening waves, loaded from a `*.waves.ron` file. Later ones repeat
+/// the last of these, growing with every wave past the end of the table.
+#[derive(Deserialize, TypeUuid, Debug)]
+#[uuid = "5c0f2d86-3a49-4b7e-9d1e-7f24c8a1b3e5"]
+pub struct WaveTable(pub Vec<WaveDefinition>);
+
+/// Fastest a zombie can get, relative to its base speed, however late the
+/// wave.
+const MAX_SPEED_MULTIPLIER: f32 = 1.6;
+
+impl WaveDefinition {
+    /// Picks which kind of zombie of the wave turns up in `biome`, by its
+    /// weight in the wave, its spawn weight and how common it is there. When
+    /// none of the wave's kinds roam the biome, it picks as if anywhere.
+    pub fn pick_kind(&self, rng: &mut impl Rng, biome: Biome) -> ZombieKind {
+        let definition = biome.definition();
+        let anywhere = |&(kind, weight): &(ZombieKind, u32)| weight * kind.archetype().spawn_weight;
+        let picked = self
+            .composition
+            .choose_weighted(rng, |entry| {
+                anywhere(entry) * definition.zombie_weight(entry.0)
+            })
+            .or_else(|_| self.composition.choose_weighted(rng, anywhere));
+        picked.unwrap().0
+    }
+}
+
+impl WaveTable {
+    /// The definition of wave `number`, counting from 1.
+    pub fn wave(&self, number: u32) -> WaveDefinition {
+        let waves = &self.0;
+        let index = (number.max(1) - 1) as usize;
+        let Some(extra) = index.checked_sub(waves.len() - 1) else {
+            return waves[index].clone();
+        };
+        let last = &waves[waves.len() - 1];
+        let extra_f = extra as f32;
+        WaveDefinition {
+            count: last.count + 4 * extra,
+            spawn_interval: (last.spawn_interval * 0.95f32.powf(extra_f)).max(0.05),
+            health_multiplier: last.health_multiplier * 1.1f32.powf(extra_f),
+            speed_multiplier: (last.speed_multiplier + 0.03 * extra_f).min(MAX_SPEED_MULTIPLIER),
+            ..last.clone()
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct WaveTableLoader;
+
+impl AssetLoader for WaveTableLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let table: WaveTable = ron::de::from_bytes(bytes)?;
+            if table.0.is_empty() {
+                return Err(bevy::asset::Error::msg("there are no waves"));
+            }
+            let empty = |wave: &WaveDefinition| {
+                wave.composition
+                    .iter()
+                    .all(|&(kind, weight)| weight * kind.archetype().spawn_weight == 0)
+            };
+            if let Some(index) = table.0.iter().position(empty) {
+                return Err(bevy::asset::Error::msg(format!(
+                    "wave {} has no zombies to pick from",
+                    index + 1
+                )));
+            }
+            load_context.set_default_asset(LoadedAsset::new(table));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["waves.ron"]
+    }
+}
+
+pub enum WavePhase {
+    /// Waiting for the next wave to start.
+    Resting(Timer),
+    /// Bringing in the wave's zombies one by one.
+    Spawning { remaining: usize, timer: Timer },
+    /// Everyone is out; the wave is cleared once they are all dead.
+    Fighting,
+}
+
+/// Paces the waves: which one is on, how far along it is, and when the next
+/// one starts.
+#[derive(Resource)]
+pub struct WaveDirector {
+    /// Number of the current or last wave, 0 before the first one.
+    pub wave: u32,
+    pub definition: WaveDefinition,
+    pub phase: WavePhase,
+}
+
+impl Default for WaveDirector {
+    fn default() -> Self {
+        Self {
+            wave: 0,
+            // Filled in from the wave table when the first wave starts.
+            definition: WaveDefinition::default(),
+            phase: WavePhase::Resting(Timer::from_seconds(1., TimerMode::Once)),
+        }
+    }
+}
+
+pub struct WaveStarted {
+    pub wave: u32,
+}
+
+pub struct WaveCleared {
+    pub wave: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> WaveTable {
+        WaveTable(vec![
+            WaveDefinition {
+                count: 5,
+                spawn_interval: 0.4,
+                health_multiplier: 1.,
+                speed_multiplier: 1.,
+                rest: 3.,
+                ..default()
+            },
+            WaveDefinition {
+                count: 10,
+                spawn_interval: 0.2,
+                health_multiplier: 2.,
+                speed_multiplier: 1.5,
+                rest: 5.,
+                ..default()
+            },
+        ])
+    }
+
+    #[test]
+    fn waves_in_the_table_are_taken_as_they_are() {
+        let table = table();
+        assert_eq!(table.wave(1).count, 5);
+        assert_eq!(table.wave(2).count, 10);
+        assert_eq!(table.wave(2).health_multiplier, 2.);
+        // There's no wave 0; it's taken for the first one.
+        assert_eq!(table.wave(0).count, 5);
+    }
+
+    #[test]
+    fn waves_past_the_table_grow_from_the_last_one() {
+        let table = table();
+        let (last, next, after) = (table.wave(2), table.wave(3), table.wave(4));
+        assert_eq!((next.count, after.count), (14, 18));
+        assert!(next.spawn_interval < last.spawn_interval);
+        assert!(after.spawn_interval < next.spawn_interval);
+        assert!(next.health_multiplier > last.health_multiplier);
+        assert!(after.health_multiplier > next.health_multiplier);
+        assert!(next.speed_multiplier > last.speed_multiplier);
+        assert_eq!(next.rest, last.rest);
+    }
+
+    #[test]
+    fn late_waves_stop_getting_faster() {
+        let wave = table().wave(200);
+        assert_eq!(wave.speed_multiplier, MAX_SPEED_MULTIPLIER);
+        assert_eq!(wave.spawn_interval, 0.05);
+    }
+}
@@ -0,0 +1,214 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::Collider;
+use serde::{Deserialize, Serialize};
+
+use crate::animations::AnimationDefinition;
+use crate::assets::AnimationAssets;
+use crate::components::{HitEffect, Projectile};
+use crate::status::StatusKind;
+
+/// Weapons the gaucho starts a run with, in slot order. The rest are
+/// unlocked along the way.
+pub const STARTING_WEAPONS: [WeaponKind; 1] = [WeaponKind::Facon];
+
+/// Also marks the attacks made with the weapon.
+#[derive(Component, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum WeaponKind {
+    Facon,
+    Boleadoras,
+    Trabuco,
+}
+
+/// How an attack reaches the zombies.
+#[derive(Clone, Copy, Debug)]
+pub enum Delivery {
+    /// A swing held next to the gaucho, `reach` pixels from his center,
+    /// that lasts as long as its animation.
+    Melee,
+    /// Thrown by hand, spinning until it hits something or flies `reach`
+    /// pixels.
+    Thrown(ProjectileSpec),
+    /// Fired from a gun, moving until it hits something or flies `reach`
+    /// pixels.
+    Ranged(ProjectileSpec),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ProjectileSpec {
+    /// Pixels per second.
+    pub speed: f32,
+    /// Seconds before it drops even if it hasn't flown its whole reach.
+    pub lifetime: f32,
+    pub pierce: usize,
+    pub on_hit: &'static [HitEffect],
+}
+
+impl ProjectileSpec {
+    pub fn projectile(&self, heading: Vec2, range: f32, hurts_gaucho: bool) -> Projectile {
+        Projectile {
+            velocity: heading * self.speed,
+            range,
+            lifetime: Timer::from_seconds(self.lifetime, TimerMode::Once),
+            pierce: self.pierce,
+            hurts_gaucho,
+            on_hit: self.on_hit,
+            hit: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Hitbox {
+    Cuboid { half_width: f32, half_height: f32 },
+    Ball { radius: f32 },
+}
+
+impl From<Hitbox> for Collider {
+    fn from(hitbox: Hitbox) -> Self {
+        match hitbox {
+            Hitbox::Cuboid {
+                half_width,
+                half_height,
+            } => Collider::cuboid(half_width, half_height),
+            Hitbox::Ball { radius } => Collider::ball(radius),
+        }
+    }
+}
+
+pub struct WeaponDefinition {
+    pub name: &'static str,
+    /// Inclusive range the damage of each attack is rolled from.
+    pub damage: (usize, usize),
+    /// Seconds between attacks.
+    pub cooldown: f32,
+    pub reach: f32,
+    pub hitbox: Hitbox,
+    /// Distance a zombie is pushed back when hit.
+    pub knockback: f32,
+    pub delivery: Delivery,
+    /// Status effects each hit applies.
+    pub inflicts: &'static [StatusKind],
+    pub sound: &'static str,
+    pub animation: fn(&AnimationAssets) -> &Handle<AnimationDefinition>,
+}
+
+impl WeaponKind {
+    /// Every weapon, in the order they're unlocked.
+    pub const ALL: [WeaponKind; 3] = [
+        WeaponKind::Facon,
+        WeaponKind::Boleadoras,
+        WeaponKind::Trabuco,
+    ];
+
+    pub fn definition(&self) -> &'static WeaponDefinition {
+        match self {
+            WeaponKind::Facon => &FACON,
+            WeaponKind::Boleadoras => &BOLEADORAS,
+            WeaponKind::Trabuco => &TRABUCO,
+        }
+    }
+}
+
+static FACON: WeaponDefinition = WeaponDefinition {
+    name: "Facón",
+    damage: (20, 80),
+    cooldown: 0.3,
+    reach: 11.,
+    hitbox: Hitbox::Cuboid {
+        half_width: 4.,
+        half_height: 5.,
+    },
+    knockback: 5.,
+    delivery: Delivery::Melee,
+    inflicts: &[StatusKind::Bleed],
+    sound: "sounds/knife_attack.ogg",
+    animation: |assets| &assets.facon,
+};
+
+static BOLEADORAS: WeaponDefinition = WeaponDefinition {
+    name: "Boleadoras",
+    damage: (30, 60),
+    cooldown: 0.8,
+    reach: 120.,
+    hitbox: Hitbox::Ball { radius: 5. },
+    knockback: 8.,
+    delivery: Delivery::Thrown(ProjectileSpec {
+        speed: 150.,
+        lifetime: 1.5,
+        pierce: 0,
+        on_hit: &[HitEffect::Sound("sounds/impact.ogg")],
+    }),
+    inflicts: &[StatusKind::Stun],
+    sound: "sounds/knife_attack.ogg",
+    animation: |assets| &assets.boleadoras,
+};
+
+static TRABUCO: WeaponDefinition = WeaponDefinition {
+    name: "Trabuco",
+    damage: (40, 90),
+    cooldown: 1.2,
+    reach: 250.,
+    hitbox: Hitbox::Ball { radius: 2. },
+    knockback: 3.,
+    delivery: Delivery::Ranged(ProjectileSpec {
+        speed: 400.,
+        lifetime: 1.,
+        pierce: 1,
+        on_hit: &[
+            HitEffect::Sound("sounds/impact.ogg"),
+            HitEffect::Splash { radius: 12. },
+        ],
+    }),
+    inflicts: &[StatusKind::Burn],
+    sound: "sounds/impact.ogg",
+    animation: |assets| &assets.trabuco_ball,
+};
+
+/// The weapons the gaucho owns and the one in his hand.
+#[derive(Component)]
+pub struct Arsenal {
+    pub owned: Vec<WeaponKind>,
+    pub equipped: usize,
+    pub cooldown: Timer,
+}
+
+impl Arsenal {
+    pub fn new(owned: Vec<WeaponKind>) -> Self {
+        let mut cooldown = Timer::from_seconds(0., TimerMode::Once);
+        cooldown.tick(cooldown.duration());
+        Self {
+            owned,
+            equipped: 0,
+            cooldown,
+        }
+    }
+
+    pub fn equipped(&self) -> WeaponKind {
+        self.owned[self.equipped]
+    }
+
+    pub fn equip(&mut self, slot: usize) {
+        if slot < self.owned.len() {
+            self.equipped = slot;
+        }
+    }
+
+    /// The next weapon to unlock, if any is still missing.
+    pub fn next_locked(&self) -> Option<WeaponKind> {
+        WeaponKind::ALL
+            .into_iter()
+            .find(|weapon| !self.owned.contains(weapon))
+    }
+
+    /// Adds the weapon in the next free slot, unless it's already owned.
+    pub fn unlock(&mut self, weapon: WeaponKind) {
+        if !self.owned.contains(&weapon) {
+            self.owned.push(weapon);
+        }
+    }
+
+    pub fn cycle(&mut self, step: isize) {
+        let len = self.owned.len() as isize;
+        self.equipped = (self.equipped as isize + step).rem_euclid(len) as usize;
+    }
+}